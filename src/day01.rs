@@ -1,6 +1,8 @@
 use itertools::{EitherOrBoth::*, Itertools};
 
-fn clean_up_line_part_1(line: &str) -> i32 {
+/// Combine the first and last digits of the line into a number, giving `None`
+/// if the line contains no digits at all
+fn clean_up_line_part_1(line: &str) -> Option<i32> {
     let mut first: Option<char> = None;
     let mut last: Option<char> = None;
 
@@ -13,14 +15,14 @@ fn clean_up_line_part_1(line: &str) -> i32 {
         }
     }
 
-    format!("{}{}", first.unwrap(), last.unwrap())
-        .parse()
-        .unwrap()
+    Some(format!("{}{}", first?, last?).parse().unwrap())
 }
 
+/// Lines without any digits (such as blank lines) are skipped, rather than
+/// causing a panic
 #[aoc(day1, part1)]
 pub fn part_1(input: &str) -> i32 {
-    input.lines().map(clean_up_line_part_1).sum()
+    input.lines().filter_map(clean_up_line_part_1).sum()
 }
 
 fn try_to_digit(chars: &mut dyn Iterator<Item = char>, expected: &str, give: u32) -> Option<u32> {
@@ -42,8 +44,13 @@ fn try_to_digit(chars: &mut dyn Iterator<Item = char>, expected: &str, give: u32
     Some(give)
 }
 
-fn process_chars(chars: &str) -> Option<u32> {
-    let mut chars_iterator = chars.chars();
+/// Find the digit (written as a word or a digit) at the start of the given
+/// chars
+///
+/// Operating on `char`s rather than bytes means that any non-ASCII characters
+/// are skipped over as a whole, rather than matching partial bytes
+fn process_chars(chars: &[char]) -> Option<u32> {
+    let mut chars_iterator = chars.iter().copied();
     let first_letter = chars_iterator.next()?;
     match first_letter {
         'o' => try_to_digit(&mut chars_iterator, "ne", 1),
@@ -78,8 +85,10 @@ fn clean_up_line_part_2(line: &str) -> i32 {
     let mut first: Option<u32> = None;
     let mut last: Option<u32> = None;
 
-    for (c_index, _) in line.char_indices() {
-        if let Some(num) = process_chars(&line[c_index..]) {
+    let chars: Vec<char> = line.chars().collect();
+
+    for c_index in 0..chars.len() {
+        if let Some(num) = process_chars(&chars[c_index..]) {
             if first.is_none() {
                 first = Some(num);
             }
@@ -99,10 +108,24 @@ pub fn part_2(input: &str) -> i32 {
 
 #[cfg(test)]
 mod test {
-    use crate::day01::clean_up_line_part_2;
+    use crate::day01::{clean_up_line_part_1, clean_up_line_part_2, part_1};
+
+    #[test]
+    fn test_part_1_skips_lines_without_digits() {
+        assert_eq!(clean_up_line_part_1("treb7uchet"), Some(77));
+        assert_eq!(clean_up_line_part_1("abc"), None);
+        assert_eq!(part_1("1abc2\n\npqr3stu8vwx\nnodigits"), 12 + 38);
+    }
 
     #[test]
     fn test_consume_strings() {
         assert_eq!(clean_up_line_part_2("two1nine"), 29)
     }
+
+    #[test]
+    fn test_unicode_line() {
+        // Non-ASCII digits are ignored, but the words around them still match
+        assert_eq!(clean_up_line_part_2("two④nine"), 29);
+        assert_eq!(clean_up_line_part_2("é3ö٤seven①"), 37);
+    }
 }
@@ -1,94 +1,239 @@
+#[cfg(feature = "json")]
+use serde::Serialize;
+
 const NUM_RED: usize = 12;
 const NUM_GREEN: usize = 13;
 const NUM_BLUE: usize = 14;
 
-fn was_game_possible(line: &str) -> bool {
-    // Get rid of "Day x:"
-    let (_, game_data) = line.split_once(": ").unwrap();
+/// The cubes revealed in a single pull from the bag
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct Pull {
+    pub red: usize,
+    pub green: usize,
+    pub blue: usize,
+}
 
-    for pull in game_data.split("; ") {
+impl Pull {
+    /// Parse a pull, giving the group of cubes that couldn't be read if it is
+    /// invalid
+    fn try_new(pull: &str) -> Result<Self, String> {
+        let mut result = Pull::default();
         for group in pull.split(", ") {
-            let (count, colour) = group.split_once(' ').unwrap();
-
-            let count = count.parse::<usize>().unwrap();
-
-            if colour == "blue" {
-                if count > NUM_BLUE {
-                    return false;
-                }
-            } else if colour == "green" {
-                if count > NUM_GREEN {
-                    return false;
-                }
-            } else if colour == "red" {
-                if count > NUM_RED {
-                    return false;
-                }
-            } else {
-                panic!("Unknown colour {colour} (count {count})");
+            let (count, colour) = group.split_once(' ').ok_or(group.to_owned())?;
+
+            let count = count.parse::<usize>().map_err(|_| group.to_owned())?;
+
+            match Colour::try_from(colour).map_err(|_| group.to_owned())? {
+                Colour::Red => result.red = count,
+                Colour::Green => result.green = count,
+                Colour::Blue => result.blue = count,
             }
         }
+        Ok(result)
+    }
+
+    fn get(&self, colour: Colour) -> usize {
+        match colour {
+            Colour::Red => self.red,
+            Colour::Green => self.green,
+            Colour::Blue => self.blue,
+        }
     }
 
-    true
+    /// Total number of cubes of any colour revealed in the pull
+    fn total(&self) -> usize {
+        self.red + self.green + self.blue
+    }
 }
 
-#[aoc(day2, part1)]
-pub fn part_1(input: &str) -> usize {
+/// A game, made up of all the pulls from the bag
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct Game {
+    pub id: usize,
+    pub pulls: Vec<Pull>,
+}
+
+/// Error when a line of the input doesn't describe a valid game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseGameError {
+    /// The line with the given number doesn't start with "Game <id>: "
+    InvalidHeader(usize),
+    /// A group of cubes on the given line isn't of the form "<count> <colour>"
+    InvalidGroup(usize, String),
+}
+
+impl Game {
+    /// Parse the game on the given line, using its line number (starting from
+    /// 1) to report any errors
+    fn try_new(line_number: usize, line: &str) -> Result<Self, ParseGameError> {
+        let (game, game_data) = line
+            .split_once(": ")
+            .ok_or(ParseGameError::InvalidHeader(line_number))?;
+        let id = game
+            .strip_prefix("Game ")
+            .and_then(|id| id.parse().ok())
+            .ok_or(ParseGameError::InvalidHeader(line_number))?;
+
+        let pulls = game_data
+            .split("; ")
+            .map(|pull| {
+                Pull::try_new(pull)
+                    .map_err(|group| ParseGameError::InvalidGroup(line_number, group))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Game { id, pulls })
+    }
+
+    /// Find the first pull in the game that shows more cubes than the limits
+    /// allow, returning the index of the pull, and the colour and count that
+    /// exceeded the limit
+    ///
+    /// Within each pull, colours are checked in the order red, green, blue
+    fn first_violation(&self, limits: CubeLimits) -> Option<(usize, Colour, usize)> {
+        self.pulls.iter().enumerate().find_map(|(i, pull)| {
+            [Colour::Red, Colour::Green, Colour::Blue]
+                .into_iter()
+                .find(|&colour| pull.get(colour) > limits.get(colour))
+                .map(|colour| (i, colour, pull.get(colour)))
+        })
+    }
+
+    /// Product of the minimum number of cubes of each colour needed to make
+    /// the game possible
+    fn power(&self) -> usize {
+        let min = |colour| self.pulls.iter().map(|p| p.get(colour)).max().unwrap_or(0);
+
+        min(Colour::Red) * min(Colour::Green) * min(Colour::Blue)
+    }
+
+    /// Total number of cubes revealed across every pull in the game
+    fn total_cubes(&self) -> usize {
+        self.pulls.iter().map(Pull::total).sum()
+    }
+}
+
+/// Parse every game in the input
+pub fn parse_games(input: &str) -> Result<Vec<Game>, ParseGameError> {
     input
         .lines()
         .enumerate()
-        .filter(|(_, line)| was_game_possible(line))
-        .map(|(i, _)| i + 1)
-        .sum()
+        .map(|(i, line)| Game::try_new(i + 1, line))
+        .collect()
+}
+
+/// Serialize each game in the input to JSON, for use by external tooling
+#[cfg(feature = "json")]
+pub fn games_to_json(input: &str) -> String {
+    serde_json::to_string(&parse_games(input).unwrap()).unwrap()
 }
 
-fn calculate_game_power(line: &str) -> usize {
-    // Get rid of "Day x:"
-    let (_, game_data) = line.split_once(": ").unwrap();
+/// Colour of a cube
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Red,
+    Green,
+    Blue,
+}
 
-    let mut min_blue = 0;
-    let mut min_red = 0;
-    let mut min_green = 0;
+impl TryFrom<&str> for Colour {
+    type Error = String;
 
-    for pull in game_data.split("; ") {
-        for group in pull.split(", ") {
-            let (count, colour) = group.split_once(' ').unwrap();
-
-            let count = count.parse::<usize>().unwrap();
-
-            if colour == "blue" {
-                if count > min_blue {
-                    min_blue = count;
-                }
-            } else if colour == "green" {
-                if count > min_green {
-                    min_green = count;
-                }
-            } else if colour == "red" {
-                if count > min_red {
-                    min_red = count;
-                }
-            } else {
-                panic!("Unknown colour {colour} (count {count})");
-            }
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "red" => Colour::Red,
+            "green" => Colour::Green,
+            "blue" => Colour::Blue,
+            _ => return Err(value.to_owned()),
+        })
+    }
+}
+
+/// The maximum number of cubes of each colour in the bag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CubeLimits {
+    pub red: usize,
+    pub green: usize,
+    pub blue: usize,
+}
+
+impl CubeLimits {
+    fn get(&self, colour: Colour) -> usize {
+        match colour {
+            Colour::Red => self.red,
+            Colour::Green => self.green,
+            Colour::Blue => self.blue,
         }
     }
+}
+
+impl Default for CubeLimits {
+    fn default() -> Self {
+        CubeLimits {
+            red: NUM_RED,
+            green: NUM_GREEN,
+            blue: NUM_BLUE,
+        }
+    }
+}
 
-    min_blue * min_green * min_red
+/// Find the first pull in the game that shows more cubes than the limits
+/// allow, returning the index of the pull, and the colour and count that
+/// exceeded the limit
+pub fn first_violation(line: &str, limits: CubeLimits) -> Option<(usize, Colour, usize)> {
+    Game::try_new(1, line)
+        .expect("Invalid game")
+        .first_violation(limits)
+}
+
+/// Total number of cubes revealed across every pull in the game on the given
+/// line
+pub fn total_cubes(line: &str) -> usize {
+    Game::try_new(1, line).expect("Invalid game").total_cubes()
+}
+
+/// Sum of the IDs of the games that are possible with the default limits
+fn sum_possible_ids(games: &[Game]) -> usize {
+    games
+        .iter()
+        .filter(|game| game.first_violation(CubeLimits::default()).is_none())
+        .map(|game| game.id)
+        .sum()
+}
+
+#[aoc(day2, part1)]
+pub fn part_1(input: &str) -> usize {
+    sum_possible_ids(&parse_games(input).unwrap())
+}
+
+/// Sum of the powers of all the games
+fn sum_powers(games: &[Game]) -> usize {
+    games.iter().map(Game::power).sum()
 }
 
 #[aoc(day2, part2)]
 pub fn part_2(input: &str) -> usize {
-    input
-        .lines()
-        .map(calculate_game_power)
-        .sum()
+    sum_powers(&parse_games(input).unwrap())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day02::was_game_possible;
+    use crate::day02::{
+        first_violation, parse_games, part_1, part_2, sum_possible_ids, sum_powers, total_cubes,
+        Colour, CubeLimits, ParseGameError,
+    };
+
+    const SAMPLE: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n\
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue\n\
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red\n\
+        Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red\n\
+        Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    fn was_game_possible(line: &str) -> bool {
+        first_violation(line, CubeLimits::default()).is_none()
+    }
 
     #[test]
     fn test_basic() {
@@ -100,4 +245,73 @@ mod test {
         ));
         assert!(!was_game_possible("Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red"))
     }
+
+    #[test]
+    fn test_first_violation() {
+        assert_eq!(
+            first_violation(
+                "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+                CubeLimits::default()
+            ),
+            Some((0, Colour::Red, 20))
+        );
+        assert_eq!(
+            first_violation(
+                "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+                CubeLimits { red: 20, green: 12, blue: 14 }
+            ),
+            Some((1, Colour::Green, 13))
+        );
+        assert_eq!(
+            first_violation(
+                "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+                CubeLimits::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_total_cubes() {
+        // 7 in the first pull, 9 in the second and 2 in the third
+        assert_eq!(
+            total_cubes("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"),
+            18
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_games_to_json() {
+        assert_eq!(
+            crate::day02::games_to_json("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"),
+            "[{\"id\":1,\"pulls\":[\
+                {\"red\":4,\"green\":0,\"blue\":3},\
+                {\"red\":1,\"green\":2,\"blue\":6},\
+                {\"red\":0,\"green\":2,\"blue\":0}\
+            ]}]"
+        )
+    }
+
+    #[test]
+    fn test_parse_games() {
+        let games = parse_games(SAMPLE).unwrap();
+        assert_eq!(games.len(), 5);
+        assert_eq!(sum_possible_ids(&games), 8);
+        assert_eq!(sum_powers(&games), 2286);
+        assert_eq!(part_1(SAMPLE), 8);
+        assert_eq!(part_2(SAMPLE), 2286);
+    }
+
+    #[test]
+    fn test_parse_games_invalid() {
+        assert_eq!(
+            parse_games("Game 1: 3 blue\nGame two: 1 red"),
+            Err(ParseGameError::InvalidHeader(2))
+        );
+        assert_eq!(
+            parse_games("Game 1: 3 blue; 4 purple"),
+            Err(ParseGameError::InvalidGroup(1, "4 purple".to_owned()))
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use itertools::Itertools;
 
 #[derive(Debug)]
@@ -23,14 +25,16 @@ fn exists_in_grid<T>(cells: &Vec<Vec<T>>, r: i32, c: i32) -> bool {
     0 <= r && 0 <= c && r < cells.len() as i32 && c < cells[0].len() as i32
 }
 
-fn line_to_cell_line(line: &str) -> Vec<Cell> {
+/// Convert a line of the input into cells, treating the given character as a
+/// gear, and reading digits in the given radix
+fn line_to_cell_line(line: &str, gear: char, radix: u32) -> Vec<Cell> {
     line.chars()
         .map(|c| match c {
             '.' => Cell::Empty,
             _ => {
-                if c.is_ascii_digit() {
-                    Cell::Number(c.to_digit(10).unwrap() as usize)
-                } else if c == '*' {
+                if let Some(digit) = c.to_digit(radix) {
+                    Cell::Number(digit as usize)
+                } else if c == gear {
                     Cell::Symbol(SymbolType::Gear)
                 } else {
                     Cell::Symbol(SymbolType::Normal)
@@ -40,151 +44,183 @@ fn line_to_cell_line(line: &str) -> Vec<Cell> {
         .collect()
 }
 
-/// only mark numbers
-fn recursive_mark(cells: &Vec<Vec<Cell>>, valid: &mut Vec<Vec<bool>>, r: i32, c: i32) {
-    if !exists_in_grid(cells, r, c) {
-        return;
-    }
-    if valid[r as usize][c as usize] {
-        return;
-    }
-    if !cells[r as usize][c as usize].is_number() {
-        return;
-    }
+/// Returns the inclusive range of columns covered by the number at the given
+/// position
+fn number_bounds(cells: &[Vec<Cell>], r: usize, c: usize) -> (usize, usize) {
+    let row = &cells[r];
+    debug_assert!(row[c].is_number(), "No number at ({r}, {c})");
 
-    valid[r as usize][c as usize] = true;
+    let start = (0..=c).rev().take_while(|&i| row[i].is_number()).last().unwrap();
+    let end = (c..row.len()).take_while(|&i| row[i].is_number()).last().unwrap();
 
-    for off_c in -1..=1 {
-        recursive_mark(cells, valid, r, c + off_c);
-    }
+    (start, end)
 }
 
-fn mark_valid_numbers(cells: &Vec<Vec<Cell>>, valid: &mut Vec<Vec<bool>>) {
-    for (r, row_data) in cells.iter().enumerate() {
-        for (c, col_data) in row_data.iter().enumerate() {
-            if matches!(col_data, Cell::Symbol(_)) {
-                for off_r in -1..=1 {
-                    for off_c in -1..=1 {
-                        recursive_mark(cells, valid, r as i32 + off_r, c as i32 + off_c);
-                    }
-                }
-            }
-        }
-    }
+fn calc_number(cells: &[Vec<Cell>], r: usize, c: usize, radix: usize) -> usize {
+    let (start, end) = number_bounds(cells, r, c);
+    cells[r][start..=end]
+        .iter()
+        .fold(0, |num, cell| match cell {
+            Cell::Number(n) => num * radix + n,
+            _ => unreachable!(),
+        })
 }
 
-fn calc_sum(cells: &[Vec<Cell>], valid: &[Vec<bool>]) -> usize {
-    let mut sum = 0;
-    for (r, row_data) in cells.iter().enumerate() {
-        let mut curr_sum: Option<usize> = None;
-        for (c, col_data) in row_data.iter().enumerate() {
-            if valid[r][c] {
-                let mut temp_sum = curr_sum.unwrap_or(0);
-                if let Cell::Number(n) = col_data {
-                    temp_sum = n + 10 * temp_sum;
-                }
-                curr_sum = Some(temp_sum);
-            } else {
-                if let Some(csum) = curr_sum {
-                    sum += csum;
-                }
-                curr_sum = None;
-            }
-        }
-        if let Some(n) = curr_sum {
-            sum += n;
-        }
-    }
-    sum
+/// Find every number in the grid, giving its value, row and the range of
+/// columns that it covers
+fn find_numbers(cells: &[Vec<Cell>], radix: usize) -> Vec<(usize, usize, Range<usize>)> {
+    cells
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row_data)| {
+            (0..row_data.len())
+                // Only look at the first digit of each number
+                .filter(move |&c| {
+                    row_data[c].is_number() && (c == 0 || !row_data[c - 1].is_number())
+                })
+                .map(move |c| {
+                    let (start, end) = number_bounds(cells, r, c);
+                    (calc_number(cells, r, c, radix), r, start..end + 1)
+                })
+        })
+        .collect_vec()
 }
 
-fn calc_number(cells: &Vec<Vec<Cell>>, r: usize, c: usize) -> usize {
-    if 1 <= c && matches!(cells[r][c - 1], Cell::Number(_)) {
-        return calc_number(cells, r, c - 1);
-    }
+/// Returns the positions of all the symbols matching the given filter
+fn find_symbols(cells: &[Vec<Cell>], filter: impl Fn(&SymbolType) -> bool) -> Vec<(usize, usize)> {
+    cells
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row_data)| {
+            row_data
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| matches!(cell, Cell::Symbol(symbol) if filter(symbol)))
+                .map(move |(c, _)| (r, c))
+        })
+        .collect_vec()
+}
 
-    // This is the first number in the sequence, so let's iterate
-    let mut num = 0;
+/// Whether the given position is next to (or inside) the number in the given
+/// row and columns
+fn is_adjacent((r, c): (usize, usize), row: usize, cols: &Range<usize>) -> bool {
+    r + 1 >= row && r <= row + 1 && c + 1 >= cols.start && c <= cols.end
+}
 
-    for cell in &cells[r][c..] {
-        if let Cell::Number(n) = cell {
-            num = num * 10 + n;
-        } else {
-            return num;
-        }
-    }
+fn calc_gear_ratio(cells: &[Vec<Cell>]) -> usize {
+    let numbers = find_numbers(cells, 10);
 
-    num
-}
-
-fn calc_gear_ratio(cells: &Vec<Vec<Cell>>) -> usize {
-    let mut sum = 0;
-
-    for (r, row_data) in cells.iter().enumerate() {
-        for (c, col_data) in row_data.iter().enumerate() {
-            if matches!(col_data, Cell::Symbol(SymbolType::Gear)) {
-                // Now check for surrounding numbers
-                let mut surround_mul = 1;
-                let mut found_nums = 0;
-
-                for off_r in -1..=1 {
-                    let mut saw_num_last = false;
-                    for off_c in -1..=1 {
-                        if !exists_in_grid(cells, r as i32 + off_r, c as i32 + off_c) {
-                            continue;
-                        }
-                        if matches!(
-                            cells[(r as i32 + off_r) as usize][(c as i32 + off_c) as usize],
-                            Cell::Number(_)
-                        ) {
-                            if saw_num_last {
-                                continue;
-                            }
-                            surround_mul *= calc_number(
-                                cells,
-                                (r as i32 + off_r) as usize,
-                                (c as i32 + off_c) as usize,
-                            );
-                            found_nums += 1;
-                            saw_num_last = true;
-                        } else {
-                            saw_num_last = false;
-                        }
-                    }
-                }
+    find_symbols(cells, |symbol| matches!(symbol, SymbolType::Gear))
+        .into_iter()
+        .map(|gear| {
+            let touching = numbers
+                .iter()
+                .filter(|(_, row, cols)| is_adjacent(gear, *row, cols))
+                .collect_vec();
 
-                // Only valid if exactly 2 numbers next to the gear
-                if found_nums == 2 {
-                    sum += surround_mul;
-                }
+            // Only valid if exactly 2 numbers next to the gear
+            if let [(a, ..), (b, ..)] = touching[..] {
+                a * b
+            } else {
+                0
             }
-        }
-    }
-
-    sum
+        })
+        .sum()
 }
 
 #[aoc(day3, part1)]
 pub fn part_1(input: &str) -> usize {
-    let cells = input.lines().map(line_to_cell_line).collect_vec();
+    part_1_radix(input, 10)
+}
 
-    let mut valid_cells = vec![vec![false; cells[0].len()]; cells.len()];
+/// Solve part 1, reading the part numbers in the given radix
+fn part_1_radix(input: &str, radix: u32) -> usize {
+    let cells = input
+        .lines()
+        .map(|line| line_to_cell_line(line, '*', radix))
+        .collect_vec();
 
-    mark_valid_numbers(&cells, &mut valid_cells);
+    let symbols = find_symbols(&cells, |_| true);
 
-    calc_sum(&cells, &valid_cells)
+    find_numbers(&cells, radix as usize)
+        .into_iter()
+        .filter(|(_, row, cols)| symbols.iter().any(|&s| is_adjacent(s, *row, cols)))
+        .map(|(value, ..)| value)
+        .sum()
+}
+
+/// Returns the value of each number in the schematic, along with the row and
+/// range of columns that it occupies
+pub fn parse_numbers(input: &str) -> Vec<(usize, usize, Range<usize>)> {
+    let cells = input
+        .lines()
+        .map(|line| line_to_cell_line(line, '*', 10))
+        .collect_vec();
+
+    find_numbers(&cells, 10)
 }
 
 #[aoc(day3, part2)]
 pub fn part_2(input: &str) -> usize {
-    let cells = input.lines().map(line_to_cell_line).collect_vec();
+    part_2_with_gear(input, '*')
+}
+
+/// Solve part 2, using the given character as the gear symbol
+fn part_2_with_gear(input: &str, gear: char) -> usize {
+    let cells = input
+        .lines()
+        .map(|line| line_to_cell_line(line, gear, 10))
+        .collect_vec();
 
     calc_gear_ratio(&cells)
 }
 
+/// Returns the (row, column) of every symbol that doesn't have any numbers
+/// next to it
+pub fn orphan_symbols(input: &str) -> Vec<(usize, usize)> {
+    let cells = input
+        .lines()
+        .map(|line| line_to_cell_line(line, '*', 10))
+        .collect_vec();
+
+    let touches_number = |r: usize, c: usize| {
+        (-1..=1).cartesian_product(-1..=1).any(|(off_r, off_c)| {
+            let (r, c) = (r as i32 + off_r, c as i32 + off_c);
+            exists_in_grid(&cells, r, c) && cells[r as usize][c as usize].is_number()
+        })
+    };
+
+    cells
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row_data)| {
+            row_data
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| matches!(cell, Cell::Symbol(_)))
+                .map(move |(c, _)| (r, c))
+        })
+        .filter(|&(r, c)| !touches_number(r, c))
+        .collect_vec()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::day03::{calc_number, part_1, Cell, part_2};
+    use crate::day03::{
+        calc_number, line_to_cell_line, number_bounds, orphan_symbols, parse_numbers, part_1,
+        part_1_radix, part_2, part_2_with_gear, Cell,
+    };
+
+    const SAMPLE: &str = "467..114..\n\
+        ...*......\n\
+        ..35..633.\n\
+        ......#...\n\
+        617*......\n\
+        .....+.58.\n\
+        ..592.....\n\
+        ......755.\n\
+        ...$.*....\n\
+        .664.598..";
 
     #[test]
     fn test_simple() {
@@ -193,21 +229,7 @@ mod test {
 
     #[test]
     fn test_advanced() {
-        assert_eq!(
-            part_1(
-                "467..114..\n\
-                ...*......\n\
-                ..35..633.\n\
-                ......#...\n\
-                617*......\n\
-                .....+.58.\n\
-                ..592.....\n\
-                ......755.\n\
-                ...$.*....\n\
-                .664.598.."
-            ),
-            4361
-        )
+        assert_eq!(part_1(SAMPLE), 4361)
     }
 
     #[test]
@@ -224,26 +246,60 @@ mod test {
 
     #[test]
     fn test_find_number() {
-        assert_eq!(calc_number(&vec![vec![Cell::Number(3), Cell::Number(2), Cell::Number(1)]], 0, 0), 321);
-        assert_eq!(calc_number(&vec![vec![Cell::Number(3), Cell::Number(2), Cell::Number(1)]], 0, 2), 321);
+        assert_eq!(calc_number(&[vec![Cell::Number(3), Cell::Number(2), Cell::Number(1)]], 0, 0, 10), 321);
+        assert_eq!(calc_number(&[vec![Cell::Number(3), Cell::Number(2), Cell::Number(1)]], 0, 2, 10), 321);
+    }
+
+    #[test]
+    fn test_hex_part_numbers() {
+        let input = "1f..a\n\
+            .*..+";
+        assert_eq!(part_1_radix(input, 16), 0x1f + 0xa);
+        // In decimal, the letters are just symbols
+        assert_eq!(part_1(input), 1);
     }
 
     #[test]
     fn test_calc_gear_ratio() {
-        assert_eq!(
-            part_2(
-                "467..114..\n\
-                ...*......\n\
-                ..35..633.\n\
-                ......#...\n\
-                617*......\n\
-                .....+.58.\n\
-                ..592.....\n\
-                ......755.\n\
-                ...$.*....\n\
-                .664.598.."
-            ),
-            467835
-        )
+        assert_eq!(part_2(SAMPLE), 467835)
+    }
+
+    #[test]
+    fn test_custom_gear() {
+        let modified = SAMPLE.replace('*', "%");
+        assert_eq!(part_2_with_gear(&modified, '%'), 467835);
+        // Once '*' isn't the gear, it's just a normal symbol
+        assert_eq!(part_2_with_gear(SAMPLE, '%'), 0);
+    }
+
+    #[test]
+    fn test_number_bounds() {
+        let cells = SAMPLE
+            .lines()
+            .map(|line| line_to_cell_line(line, '*', 10))
+            .collect::<Vec<_>>();
+        assert_eq!(number_bounds(&cells, 2, 6), (6, 8));
+        assert_eq!(number_bounds(&cells, 2, 7), (6, 8));
+        assert_eq!(number_bounds(&cells, 2, 8), (6, 8));
+    }
+
+    #[test]
+    fn test_orphan_symbols() {
+        let input = "12*34\n\
+            .....\n\
+            ..#..\n\
+            .....\n\
+            +...5";
+        assert_eq!(orphan_symbols(input), [(2, 2), (4, 0)]);
+        assert!(orphan_symbols(SAMPLE).is_empty());
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        let numbers = parse_numbers(SAMPLE);
+        assert_eq!(numbers.len(), 10);
+        assert_eq!(numbers[0], (467, 0, 0..3));
+        assert_eq!(numbers[1], (114, 0, 5..8));
+        assert_eq!(numbers[9], (598, 9, 5..8));
     }
 }
@@ -1,62 +1,94 @@
-use std::str::Lines;
+use std::{collections::HashMap, num::NonZeroU64, str::Lines};
 
 use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Range {
-    start: i64,
-    length: i64,
+pub struct Range {
+    pub start: i64,
+    pub length: i64,
 }
 
+/// Error given when mapping a value would take it outside the range of `i64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+/// A single line of a map, sending `length` values starting from `input` to
+/// the values starting from `output`
 #[derive(Debug)]
-struct RangeMap {
-    input: i64,
-    output: i64,
-    length: i64,
+pub struct RangeMap {
+    pub input: i64,
+    pub output: i64,
+    pub length: i64,
 }
 
 impl RangeMap {
-    fn get(&self, input: i64) -> Option<i64> {
-        if self.input <= input && input < self.input + self.length {
-            Some(input - self.input + self.output)
+    /// End of the input range (exclusive), clamped so that it can't overflow
+    fn input_end(&self) -> i64 {
+        self.input.saturating_add(self.length)
+    }
+
+    /// Map a value known to be within the input range
+    fn map_value(&self, input: i64) -> Result<i64, Overflow> {
+        input
+            .checked_sub(self.input)
+            .and_then(|offset| offset.checked_add(self.output))
+            .ok_or(Overflow)
+    }
+
+    /// Map a range known to start within the input range, giving an error if
+    /// any part of the result would overflow
+    fn map_range(&self, start: i64, length: i64) -> Result<Range, Overflow> {
+        let start = self.map_value(start)?;
+        start.checked_add(length).ok_or(Overflow)?;
+        Ok(Range { start, length })
+    }
+
+    /// Map the value, giving `None` if it isn't within the input range, or an
+    /// error if the result would overflow
+    fn get(&self, input: i64) -> Result<Option<i64>, Overflow> {
+        if self.input <= input && input < self.input_end() {
+            self.map_value(input).map(Some)
         } else {
-            None
+            Ok(None)
         }
     }
 
     // Return (mapped range, any remaining range)
-    fn get_range(&self, input: &Range) -> (Option<Range>, Option<Range>) {
+    //
+    // If the mapped range would overflow, gives an error instead
+    fn get_range(&self, input: &Range) -> Result<(Option<Range>, Option<Range>), Overflow> {
         // Start in range
-        if self.input <= input.start && input.start < self.input + self.length {
+        if self.input <= input.start && input.start < self.input_end() {
             // Fully in range
-            if input.start + input.length <= self.input + self.length {
-                (
-                    Some(Range {
-                        start: input.start - self.input + self.output,
-                        length: input.length,
-                    }),
-                    None,
-                )
+            if input.start.saturating_add(input.length) <= self.input_end() {
+                Ok((Some(self.map_range(input.start, input.length)?), None))
             } else {
                 // Doesn't fit fully in range
-                let new_length =
-                    input.length - ((input.start + input.length) - (self.input + self.length));
+                let new_length = self.input_end() - input.start;
 
-                (
+                Ok((
                     // Mapped range
-                    Some(Range {
-                        start: input.start - self.input + self.output,
-                        length: new_length,
-                    }),
+                    Some(self.map_range(input.start, new_length)?),
                     // Remaining range
                     Some(Range {
                         start: input.start + new_length,
                         length: input.length - new_length,
                     }),
-                )
+                ))
             }
         } else {
-            (None, Some(input.clone()))
+            Ok((None, Some(input.clone())))
+        }
+    }
+}
+
+impl RangeMap {
+    /// Return the map that undoes this one
+    fn inverted(&self) -> RangeMap {
+        RangeMap {
+            input: self.output,
+            output: self.input,
+            length: self.length,
         }
     }
 }
@@ -73,17 +105,30 @@ impl From<&str> for RangeMap {
     }
 }
 
+/// Map the value using the first of the maps whose input range contains it,
+/// leaving it unchanged if there aren't any
+fn lookup(maps: &[RangeMap], v: i64) -> Result<i64, Overflow> {
+    for map in maps {
+        if let Some(mapped) = map.get(v)? {
+            return Ok(mapped);
+        }
+    }
+    Ok(v)
+}
+
+/// Parse the list of seeds, which may be empty
 fn parse_seed_list(line: &str) -> Vec<i64> {
-    line.split_once(": ")
+    line.split_once(':')
         .unwrap()
         .1
-        .split(' ')
+        .split_whitespace()
         .map(|n| n.parse().unwrap())
         .collect_vec()
 }
 
-fn parse_seed_list_part_2(line: &str) -> Vec<Range> {
-    parse_seed_list(line)
+/// Interpret a list of seeds as pairs of starts and lengths
+fn seed_list_to_ranges(seeds: &[i64]) -> Vec<Range> {
+    seeds
         .chunks(2)
         .map(|c| {
             let start = c[0];
@@ -93,10 +138,12 @@ fn parse_seed_list_part_2(line: &str) -> Vec<Range> {
         .collect_vec()
 }
 
-fn read_mapping(lines: &mut Lines<'_>) -> Option<Vec<RangeMap>> {
-    if !lines.next()?.contains(" map:") {
-        return None;
-    }
+/// Read a map block, returning the names of the categories it maps from and
+/// to, along with its ranges
+///
+/// A block with no ranges maps every value to itself.
+fn read_mapping<'a>(lines: &mut Lines<'a>) -> Option<(&'a str, &'a str, Vec<RangeMap>)> {
+    let (from, to) = lines.next()?.strip_suffix(" map:")?.split_once("-to-")?;
 
     let mut mappings = vec![];
 
@@ -107,186 +154,576 @@ fn read_mapping(lines: &mut Lines<'_>) -> Option<Vec<RangeMap>> {
         mappings.push(RangeMap::from(line));
     }
 
-    Some(mappings)
+    Some((from, to, mappings))
 }
 
-#[aoc(day5, part1)]
-pub fn part_1(input: &str) -> i64 {
-    let mut lines = input.lines();
+/// A single stage of the almanac, mapping values from one category to the
+/// next
+#[derive(Debug)]
+pub struct MapStage {
+    name: String,
+    ranges: Vec<RangeMap>,
+}
 
-    let mut seeds = parse_seed_list(lines.next().unwrap());
+impl MapStage {
+    /// Name of the stage, eg `seed-to-soil`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Map a single value through this stage
+    pub fn apply(&self, v: i64) -> Result<i64, Overflow> {
+        lookup(&self.ranges, v)
+    }
 
-    // Skip empty line
-    lines.next();
+    /// Map a range of values through this stage, which may split it into
+    /// multiple ranges
+    pub fn apply_range(&self, r: Range) -> Result<Vec<Range>, Overflow> {
+        transpose_range(r, &self.ranges)
+    }
 
-    let mut mappings_vec = vec![];
+    /// Split the values from `start` to `end` (exclusive) into pieces whose
+    /// values are all shifted by the same amount by this stage, giving
+    /// `(start, end, shift)` for each piece
+    ///
+    /// Values near the limits of `i64` are split off wherever mapping them
+    /// would start to overflow, giving an error rather than a shift for them.
+    fn split_by_shift(&self, start: i128, end: i128) -> Vec<(i128, i128, Result<i128, Overflow>)> {
+        let mut boundaries = vec![start, end];
+        for map in &self.ranges {
+            let (input, output) = (map.input as i128, map.output as i128);
+            boundaries.extend([
+                input,
+                map.input_end() as i128,
+                i64::MAX as i128 + input + 1,
+                i64::MAX as i128 - output + input + 1,
+            ]);
+        }
 
-    while let Some(map) = read_mapping(&mut lines) {
-        mappings_vec.push(map);
+        boundaries
+            .into_iter()
+            .filter(|b| (start..=end).contains(b))
+            .sorted()
+            .dedup()
+            .tuple_windows()
+            .map(|(s, e)| {
+                // Every value in the piece is mapped the same way as its start
+                let mapped = self
+                    .ranges
+                    .iter()
+                    .find_map(|map| map.get(s as i64).transpose())
+                    .transpose();
+                (s, e, mapped.map(|m| m.map_or(0, |m| m as i128 - s)))
+            })
+            .collect_vec()
     }
 
-    for mapping in mappings_vec {
-        let mut new_values = vec![];
+    /// Return the stage that undoes this one
+    fn inverted(&self) -> MapStage {
+        MapStage {
+            name: self.name.clone(),
+            ranges: self.ranges.iter().map(RangeMap::inverted).collect_vec(),
+        }
+    }
+}
 
-        for seed in seeds {
-            let mut new_val = None;
-            for range in &mapping {
-                new_val = range.get(seed);
-                if new_val.is_some() {
-                    break;
-                }
-            }
-            new_values.push(new_val.unwrap_or(seed));
+/// The full puzzle input: the list of seeds, followed by each map stage
+#[derive(Debug)]
+pub struct Almanac {
+    seeds: Vec<i64>,
+    /// Stages ordered so that they can be applied one after the other,
+    /// starting from seeds
+    stages: Vec<MapStage>,
+}
+
+impl Almanac {
+    /// Find the stage with the given name
+    pub fn stage(&self, name: &str) -> Option<&MapStage> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+
+    /// Iterate over the name and ranges of each stage, in the order that they
+    /// are applied
+    pub fn stages(&self) -> impl Iterator<Item = (&str, &[RangeMap])> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.name(), stage.ranges.as_slice()))
+    }
+
+    /// Combine all the stages into a single map from seeds to locations, so
+    /// that each seed only needs to be looked up once. Values that aren't
+    /// covered by any of the maps are unchanged.
+    fn compose(&self) -> ComposedMap {
+        // Pieces of seed values which have all been shifted by the same amount
+        // by the stages so far
+        let mut pieces = vec![(i64::MIN as i128, i64::MAX as i128 + 1, Ok(0))];
+
+        for stage in &self.stages {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|(start, end, shift)| match shift {
+                    Ok(shift) => stage
+                        .split_by_shift(start + shift, end + shift)
+                        .into_iter()
+                        .map(|(s, e, next)| (s - shift, e - shift, next.map(|n| shift + n)))
+                        .collect_vec(),
+                    // Once a piece overflows, it can't be mapped any further
+                    Err(e) => vec![(start, end, Err(e))],
+                })
+                .collect_vec();
         }
 
-        seeds = new_values;
+        let (maps, overflowing): (Vec<_>, Vec<_>) = pieces
+            .into_iter()
+            .filter(|(_, _, shift)| *shift != Ok(0))
+            .partition(|(_, _, shift)| shift.is_ok());
+
+        ComposedMap {
+            maps: maps
+                .into_iter()
+                .map(|(start, end, shift)| RangeMap {
+                    input: start as i64,
+                    output: (start + shift.unwrap()) as i64,
+                    length: (end - start) as i64,
+                })
+                .collect_vec(),
+            overflowing: overflowing
+                .into_iter()
+                .map(|(start, end, _)| (start, end))
+                .collect_vec(),
+        }
     }
 
-    seeds.iter().min().unwrap().to_owned()
+    /// Interpret the seeds as ranges, as required by part 2
+    fn seed_ranges(&self) -> Vec<Range> {
+        seed_list_to_ranges(&self.seeds)
+    }
+}
+
+/// All the stages of an almanac combined into one, from seeds to locations
+struct ComposedMap {
+    maps: Vec<RangeMap>,
+    /// Ranges of seeds, from `start` to `end` (exclusive), which overflow at
+    /// some stage
+    overflowing: Vec<(i128, i128)>,
+}
+
+impl ComposedMap {
+    /// Map a seed to its location
+    fn get(&self, seed: i64) -> Result<i64, Overflow> {
+        let seed_i128 = seed as i128;
+        if self
+            .overflowing
+            .iter()
+            .any(|&(start, end)| start <= seed_i128 && seed_i128 < end)
+        {
+            return Err(Overflow);
+        }
+        lookup(&self.maps, seed)
+    }
+}
+
+impl From<&str> for Almanac {
+    /// Parse the almanac
+    ///
+    /// The map blocks may appear in any order in the input, since they are
+    /// chained together by following their names. Panics if the chain
+    /// doesn't lead from seeds to locations, or leaves some maps unused.
+    fn from(input: &str) -> Self {
+        let mut lines = input.lines();
+
+        let seeds = parse_seed_list(lines.next().unwrap());
+
+        // Skip empty line
+        lines.next();
+
+        let mut maps_by_source = HashMap::new();
+
+        while let Some((from, to, map)) = read_mapping(&mut lines) {
+            maps_by_source.insert(from, (to, map));
+        }
+
+        let mut stages = vec![];
+        let mut category = "seed";
+
+        while let Some((to, ranges)) = maps_by_source.remove(category) {
+            stages.push(MapStage {
+                name: format!("{category}-to-{to}"),
+                ranges,
+            });
+            category = to;
+        }
+
+        assert!(
+            maps_by_source.is_empty(),
+            "Maps not reachable from seeds: {:?}",
+            maps_by_source.keys().sorted().collect_vec()
+        );
+        assert_eq!(category, "location", "Maps don't lead to locations");
+
+        Almanac { seeds, stages }
+    }
+}
+
+/// Lowest location given when there are no seeds to plant
+pub const NO_SEEDS: i64 = i64::MAX;
+
+/// Returns the `(seed, location)` for each seed in the seed list, treating
+/// them as individual seeds as in part 1, sorted by location
+///
+/// Gives an error if mapping any of the seeds would overflow.
+pub fn seed_locations(input: &str) -> Result<Vec<(i64, i64)>, Overflow> {
+    let almanac = Almanac::from(input);
+    let composed = almanac.compose();
+
+    Ok(almanac
+        .seeds
+        .iter()
+        .map(|&seed| Ok((seed, composed.get(seed)?)))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .sorted_by_key(|&(seed, location)| (location, seed))
+        .collect_vec())
+}
+
+#[aoc(day5, part1)]
+pub fn part_1(input: &str) -> i64 {
+    seed_locations(input)
+        .unwrap()
+        .first()
+        .map_or(NO_SEEDS, |&(_, location)| location)
 }
 
-fn transpose_range(range: Range, mappings: &Vec<RangeMap>) -> Vec<Range> {
+fn transpose_range(range: Range, mappings: &Vec<RangeMap>) -> Result<Vec<Range>, Overflow> {
     for map in mappings {
-        match map.get_range(&range) {
+        match map.get_range(&range)? {
             (None, None) => panic!("BRUH"),
-            (None, Some(_)) => continue,
-            (Some(output), None) => return vec![output],
+            (None, Some(_)) => {
+                // The range may still overlap with the start of the map, in
+                // which case, split it so that each half can be handled
+                // separately
+                if range.start < map.input && map.input < range.start + range.length {
+                    let before = Range {
+                        start: range.start,
+                        length: map.input - range.start,
+                    };
+                    let after = Range {
+                        start: map.input,
+                        length: range.length - before.length,
+                    };
+                    let mut v = transpose_range(before, mappings)?;
+                    v.extend(transpose_range(after, mappings)?);
+                    return Ok(v);
+                }
+                continue;
+            }
+            (Some(output), None) => return Ok(vec![output]),
             (Some(output), Some(remainder)) => {
                 let mut v = vec![output];
 
                 // Handle the remaining range
-                v.extend(transpose_range(remainder, mappings));
+                v.extend(transpose_range(remainder, mappings)?);
 
-                return v;
+                return Ok(v);
             }
         }
     }
 
-    vec![range]
+    Ok(vec![range])
 }
 
-#[aoc(day5, part2)]
-pub fn part_2(input: &str) -> i64 {
-    let mut lines = input.lines();
+/// Returns all the location ranges reached by the seed ranges, sorted by
+/// their start
+///
+/// Gives an error if mapping any of the ranges would overflow.
+pub fn location_ranges(input: &str) -> Result<Vec<Range>, Overflow> {
+    let almanac = Almanac::from(input);
 
-    let mut seeds = parse_seed_list_part_2(lines.next().unwrap());
+    let mut seeds = almanac.seed_ranges();
 
-    // Skip empty line
-    lines.next();
+    for stage in &almanac.stages {
+        let mut new_values = vec![];
 
-    let mut mappings_vec = vec![];
+        for seed_range in seeds {
+            new_values.extend(stage.apply_range(seed_range)?);
+        }
 
-    while let Some(map) = read_mapping(&mut lines) {
-        mappings_vec.push(map);
+        seeds = new_values;
     }
 
-    for mapping in mappings_vec {
+    seeds.sort_by_key(|r| r.start);
+    Ok(seeds)
+}
+
+/// Returns the overlapping section of two ranges, if any
+fn intersect(a: &Range, b: &Range) -> Option<Range> {
+    let start = a.start.max(b.start);
+    let end = (a.start + a.length).min(b.start + b.length);
+    if start < end {
+        Some(Range {
+            start,
+            length: end - start,
+        })
+    } else {
+        None
+    }
+}
+
+/// Returns the seed ranges that map into the given location range, sorted by
+/// their start
+///
+/// This works by running the range backwards through inverted maps, so
+/// assumes that each map is a one-to-one mapping. Gives an error if mapping
+/// any of the ranges would overflow.
+pub fn seeds_reaching(input: &str, loc_range: Range) -> Result<Vec<Range>, Overflow> {
+    let almanac = Almanac::from(input);
+
+    let seeds = almanac.seed_ranges();
+
+    let mut ranges = vec![loc_range];
+
+    for stage in almanac.stages.iter().rev() {
+        let inverted = stage.inverted();
         let mut new_values = vec![];
 
-        for seed_range in seeds {
-            new_values.extend(transpose_range(seed_range, &mapping));
+        for range in ranges {
+            new_values.extend(inverted.apply_range(range)?);
         }
 
-        seeds = new_values;
+        ranges = new_values;
     }
 
-    seeds
+    // Only keep the parts that are seeds we actually have
+    Ok(ranges
         .iter()
-        .flat_map(|r| (r.start)..(r.start + r.length))
-        .min()
+        .cartesian_product(&seeds)
+        .filter_map(|(range, seed_range)| intersect(range, seed_range))
+        .sorted_by_key(|r| r.start)
+        .collect_vec())
+}
+
+/// Returns the number of seeds whose location lands in each bucket of
+/// `bucket_size` locations, keyed by the first location of the bucket
+///
+/// Gives an error if mapping any of the seeds would overflow, or if the buckets
+/// don't fit in an `i64`.
+pub fn location_histogram(
+    input: &str,
+    bucket_size: NonZeroU64,
+) -> Result<HashMap<i64, usize>, Overflow> {
+    let bucket_size = i64::try_from(bucket_size.get()).map_err(|_| Overflow)?;
+    let mut histogram = HashMap::new();
+
+    for range in location_ranges(input)? {
+        let end = range.start + range.length;
+        let mut start = range.start;
+        // Split the range at each bucket boundary it crosses
+        while start < end {
+            let bucket = start
+                .div_euclid(bucket_size)
+                .checked_mul(bucket_size)
+                .ok_or(Overflow)?;
+            let bucket_end = end.min(bucket.saturating_add(bucket_size));
+            *histogram.entry(bucket).or_default() += (bucket_end - start) as usize;
+            start = bucket_end;
+        }
+    }
+
+    Ok(histogram)
+}
+
+#[aoc(day5, part2)]
+pub fn part_2(input: &str) -> i64 {
+    location_ranges(input)
         .unwrap()
-        .to_owned()
+        .iter()
+        .map(|r| r.start)
+        .min()
+        .unwrap_or(NO_SEEDS)
 }
 
 #[cfg(test)]
 mod test {
     // use crate::day5::part_2;
 
-    use crate::day05::{part_1, part_2};
-
-    use super::{Range, RangeMap};
+    use crate::day05::{
+        location_histogram, location_ranges, part_1, part_2, seed_locations, seeds_reaching,
+        Overflow, NO_SEEDS,
+    };
+
+    use std::{collections::HashMap, num::NonZeroU64};
+
+    use super::{Almanac, Range, RangeMap};
+    use itertools::Itertools;
+
+    const SAMPLE: &str = "seeds: 79 14 55 13\n\
+        \n\
+        seed-to-soil map:\n\
+        50 98 2\n\
+        52 50 48\n\
+        \n\
+        soil-to-fertilizer map:\n\
+        0 15 37\n\
+        37 52 2\n\
+        39 0 15\n\
+        \n\
+        fertilizer-to-water map:\n\
+        49 53 8\n\
+        0 11 42\n\
+        42 0 7\n\
+        57 7 4\n\
+        \n\
+        water-to-light map:\n\
+        88 18 7\n\
+        18 25 70\n\
+        \n\
+        light-to-temperature map:\n\
+        45 77 23\n\
+        81 45 19\n\
+        68 64 13\n\
+        \n\
+        temperature-to-humidity map:\n\
+        0 69 1\n\
+        1 0 69\n\
+        \n\
+        humidity-to-location map:\n\
+        60 56 37\n\
+        56 93 4";
 
     #[test]
     fn test_part_1() {
+        assert_eq!(part_1(SAMPLE), 35);
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(part_2(SAMPLE), 46);
+    }
+
+    #[test]
+    fn test_shuffled_maps() {
+        let mut blocks = SAMPLE.split("\n\n").collect_vec();
+        blocks[1..].reverse();
+        let shuffled = blocks.join("\n\n");
+        assert_eq!(part_1(&shuffled), 35);
+        assert_eq!(part_2(&shuffled), 46);
+    }
+
+    #[test]
+    fn test_seed_locations() {
+        let locations = seed_locations(SAMPLE).unwrap();
+        assert_eq!(locations, [(13, 35), (14, 43), (79, 82), (55, 86)]);
+    }
+
+    #[test]
+    fn test_empty_seeds() {
+        let input = SAMPLE.replacen("seeds: 79 14 55 13", "seeds:", 1);
+        assert_eq!(part_1(&input), NO_SEEDS);
+        assert_eq!(part_2(&input), NO_SEEDS);
+        assert!(location_ranges(&input).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_empty_map() {
+        let input = "seeds: 1 2\n\
+            \n\
+            seed-to-soil map:\n\
+            \n\
+            soil-to-fertilizer map:\n\
+            10 0 5\n\
+            \n\
+            fertilizer-to-location map:";
+        let almanac = Almanac::from(input);
+        assert_eq!(almanac.stages().count(), 3);
+        assert_eq!(almanac.stage("seed-to-soil").unwrap().apply(7), Ok(7));
+        assert_eq!(part_1(input), 11);
+        assert_eq!(part_2(input), 11);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unreachable_map() {
+        let _ = Almanac::from(
+            "seeds: 1 2\n\
+            \n\
+            seed-to-location map:\n\
+            \n\
+            soil-to-location map:",
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chain_not_ending_at_location() {
+        let _ = Almanac::from(
+            "seeds: 1 2\n\
+            \n\
+            seed-to-soil map:",
+        );
+    }
+
+    #[test]
+    fn test_map_stage() {
+        let almanac = Almanac::from(SAMPLE);
+        let stage = almanac.stage("seed-to-soil").unwrap();
+        assert_eq!(stage.name(), "seed-to-soil");
+        assert_eq!(stage.apply(79), Ok(81));
         assert_eq!(
-            part_1(
-                "seeds: 79 14 55 13\n\
-                \n\
-                seed-to-soil map:\n\
-                50 98 2\n\
-                52 50 48\n\
-                \n\
-                soil-to-fertilizer map:\n\
-                0 15 37\n\
-                37 52 2\n\
-                39 0 15\n\
-                \n\
-                fertilizer-to-water map:\n\
-                49 53 8\n\
-                0 11 42\n\
-                42 0 7\n\
-                57 7 4\n\
-                \n\
-                water-to-light map:\n\
-                88 18 7\n\
-                18 25 70\n\
-                \n\
-                light-to-temperature map:\n\
-                45 77 23\n\
-                81 45 19\n\
-                68 64 13\n\
-                \n\
-                temperature-to-humidity map:\n\
-                0 69 1\n\
-                1 0 69\n\
-                \n\
-                humidity-to-location map:\n\
-                60 56 37\n\
-                56 93 4"
-            ),
-            35,
+            stage.apply_range(Range { start: 96, length: 4 }).unwrap(),
+            [Range { start: 98, length: 2 }, Range { start: 50, length: 2 }]
         );
     }
 
     #[test]
-    fn test_part_2() {
+    fn test_compose() {
+        let almanac = Almanac::from(SAMPLE);
+        let composed = almanac.compose();
+        let sequential = |seed| {
+            almanac
+                .stages
+                .iter()
+                .try_fold(seed, |v, stage| stage.apply(v))
+        };
+
+        for seed in almanac.seeds.iter().copied().chain(0..200) {
+            assert_eq!(composed.get(seed), sequential(seed), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_almanac_stages() {
+        let almanac = Almanac::from(SAMPLE);
+        assert_eq!(
+            almanac.stages().map(|(name, _)| name).collect_vec(),
+            [
+                "seed-to-soil",
+                "soil-to-fertilizer",
+                "fertilizer-to-water",
+                "water-to-light",
+                "light-to-temperature",
+                "temperature-to-humidity",
+                "humidity-to-location",
+            ]
+        );
+        let (_, ranges) = almanac.stages().next().unwrap();
         assert_eq!(
-            part_2(
-                "seeds: 79 14 55 13\n\
-                \n\
-                seed-to-soil map:\n\
-                50 98 2\n\
-                52 50 48\n\
-                \n\
-                soil-to-fertilizer map:\n\
-                0 15 37\n\
-                37 52 2\n\
-                39 0 15\n\
-                \n\
-                fertilizer-to-water map:\n\
-                49 53 8\n\
-                0 11 42\n\
-                42 0 7\n\
-                57 7 4\n\
-                \n\
-                water-to-light map:\n\
-                88 18 7\n\
-                18 25 70\n\
-                \n\
-                light-to-temperature map:\n\
-                45 77 23\n\
-                81 45 19\n\
-                68 64 13\n\
-                \n\
-                temperature-to-humidity map:\n\
-                0 69 1\n\
-                1 0 69\n\
-                \n\
-                humidity-to-location map:\n\
-                60 56 37\n\
-                56 93 4"
-            ),
-            46,
+            (ranges[0].input, ranges[0].output, ranges[0].length),
+            (98, 50, 2)
+        );
+    }
+
+    #[test]
+    fn test_location_ranges() {
+        let ranges = location_ranges(SAMPLE).unwrap();
+        assert_eq!(ranges[0].start, 46);
+        assert!(ranges.windows(2).all(|w| w[0].start <= w[1].start));
+    }
+
+    #[test]
+    fn test_seeds_reaching() {
+        assert_eq!(
+            seeds_reaching(SAMPLE, Range { start: 46, length: 1 }),
+            Ok(vec![Range { start: 82, length: 1 }])
         );
     }
 
@@ -297,7 +734,7 @@ mod test {
         let map = RangeMap { input: 0, output: 1, length: 5 };
 
         assert_eq!(
-            map.get_range(&r),
+            map.get_range(&r).unwrap(),
             (
                 Some(Range { start: 1, length: 5 }),
                 None
@@ -312,7 +749,7 @@ mod test {
         let map = RangeMap { input: 5, output: 1, length: 5 };
 
         assert_eq!(
-            map.get_range(&r),
+            map.get_range(&r).unwrap(),
             (
                 None,
                 Some(r)
@@ -327,11 +764,90 @@ mod test {
         let map = RangeMap { input: 0, output: 1, length: 5 };
 
         assert_eq!(
-            map.get_range(&r),
+            map.get_range(&r).unwrap(),
             (
                 Some(Range { start: 4, length: 2 }),
                 Some(Range { start: 5, length: 3 })
             )
         )
     }
+
+    #[test]
+    fn test_map_near_overflow() {
+        let map = RangeMap { input: 0, output: i64::MAX - 10, length: 20 };
+
+        assert_eq!(map.get(5), Ok(Some(i64::MAX - 5)));
+        assert_eq!(map.get(15), Err(Overflow));
+        assert_eq!(
+            map.get_range(&Range { start: 0, length: 5 }),
+            Ok((
+                Some(Range { start: i64::MAX - 10, length: 5 }),
+                None
+            ))
+        );
+        assert_eq!(
+            map.get_range(&Range { start: 0, length: 20 }),
+            Err(Overflow)
+        );
+
+        // The end of the input range itself overflows
+        let map = RangeMap { input: i64::MAX - 5, output: 0, length: 10 };
+
+        assert_eq!(map.get(i64::MAX - 1), Ok(Some(4)));
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let input = "seeds: 5 15\n\
+            \n\
+            seed-to-location map:\n\
+            9223372036854775797 0 20";
+        assert_eq!(seed_locations(input), Err(Overflow));
+        assert!(location_ranges(input).is_err());
+
+        // Seeds that stay clear of the overflow are mapped as usual
+        let input = input.replacen("5 15", "5 1", 1);
+        assert_eq!(
+            seed_locations(&input),
+            Ok(vec![(1, i64::MAX - 9), (5, i64::MAX - 5)])
+        );
+        assert_eq!(part_2(&input), i64::MAX - 5);
+    }
+
+    #[test]
+    fn test_location_histogram() {
+        let bucket_size = |size| NonZeroU64::new(size).unwrap();
+        let histogram = location_histogram(SAMPLE, bucket_size(10)).unwrap();
+        assert_eq!(
+            histogram.into_iter().sorted().collect_vec(),
+            [(40, 4), (50, 10), (60, 1), (80, 7), (90, 5)]
+        );
+        // Every seed is counted exactly once
+        assert_eq!(
+            location_histogram(SAMPLE, bucket_size(7))
+                .unwrap()
+                .values()
+                .sum::<usize>(),
+            14 + 13
+        );
+    }
+
+    #[test]
+    fn test_location_histogram_limits() {
+        let bucket_size = |size| NonZeroU64::new(size).unwrap();
+        assert_eq!(
+            location_histogram(SAMPLE, bucket_size(u64::MAX)),
+            Err(Overflow)
+        );
+
+        let input = "seeds: -9223372036854775808 1\n\
+            \n\
+            seed-to-location map:";
+        assert_eq!(
+            location_histogram(input, bucket_size(2)),
+            Ok(HashMap::from([(i64::MIN, 1)]))
+        );
+        // The bucket containing the lowest location would start below it
+        assert_eq!(location_histogram(input, bucket_size(3)), Err(Overflow));
+    }
 }
@@ -1,46 +1,190 @@
 use itertools::Itertools;
 
-fn read_row(line: &str) -> Vec<i64> {
-    line.split_once(": ")
-        .unwrap()
-        .1
-        .trim()
+/// Find the line with the given label (such as `"Time"`), returning the
+/// values after the label
+fn labelled_row<'a>(input: &'a str, label: &str) -> &'a str {
+    input
+        .lines()
+        .find_map(|line| line.strip_prefix(label)?.strip_prefix(':'))
+        .unwrap_or_else(|| panic!("Missing {label} line"))
+}
+
+fn read_row(values: &str) -> Vec<i64> {
+    values
         .split_ascii_whitespace()
         .map(|n| n.parse().unwrap())
         .collect()
 }
 
 fn num_winning_combos(time: i64, distance: i64) -> i64 {
-    let mut num_wins = 0;
-    for wait_time in 1..(time - 1) {
-        let our_dist = wait_time * (time - wait_time);
-        if our_dist > distance {
-            num_wins += 1;
+    // Work in i128 so that `wait_time * (time - wait_time)` can't overflow,
+    // even for races near `i64::MAX`
+    let time = time as i128;
+    let distance = distance as i128;
+    let beats_record = |wait_time: i128| wait_time * (time - wait_time) > distance;
+
+    // Distance is maximised by waiting for half the race
+    let best_wait = time / 2;
+    if !beats_record(best_wait) {
+        return 0;
+    }
+
+    // Distance increases up until `best_wait`, so binary search for the
+    // shortest winning wait time (`low` always loses, `high` always wins)
+    let mut low = 0;
+    let mut high = best_wait;
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if beats_record(mid) {
+            high = mid;
+        } else {
+            low = mid;
         }
     }
-    num_wins
+
+    // Winning wait times are symmetric around the middle of the race
+    (time - 2 * high + 1) as i64
 }
 
-#[aoc(day6, part1)]
-pub fn part_1(input: &str) -> i64 {
-    let (times, distances) = input.lines().collect_tuple().unwrap();
+/// Returns the `(hold, distance)` for the hold time that travels furthest in a
+/// race of the given length
+///
+/// For odd times, holding for one more millisecond gives the same distance.
+/// Gives `None` if the distance is too far to fit in an `i64`.
+pub fn optimal_hold(time: i64) -> Option<(i64, i64)> {
+    let hold = time / 2;
+    // Work in i128 so that the distance can't overflow before it is checked
+    let distance = hold as i128 * (time - hold) as i128;
+    Some((hold, distance.try_into().ok()?))
+}
+
+/// Returns the product of the number of ways to win each (time, distance)
+/// race
+pub fn solve_races(races: &[(i64, i64)]) -> i64 {
+    races
+        .iter()
+        .map(|&(t, d)| num_winning_combos(t, d))
+        .product()
+}
 
-    let times = read_row(times);
-    let distances = read_row(distances);
+/// Returns the number of ways to win each race, reading the races as separate
+/// columns like in part 1
+pub fn per_race_counts(input: &str) -> Vec<i64> {
+    let times = read_row(labelled_row(input, "Time"));
+    let distances = read_row(labelled_row(input, "Distance"));
 
     times
         .into_iter()
         .zip(distances)
         .map(|(t, d)| num_winning_combos(t, d))
-        .product()
+        .collect_vec()
+}
+
+#[aoc(day6, part1)]
+pub fn part_1(input: &str) -> i64 {
+    per_race_counts(input).into_iter().product()
 }
 
 #[aoc(day6, part2)]
 pub fn part_2(input: &str) -> i64 {
-    let (times, distances) = input.lines().collect_tuple().unwrap();
-
-    let time = times.split_once(": ").unwrap().1.replace(' ', "").parse().unwrap();
-    let distance = distances.split_once(": ").unwrap().1.replace(' ', "").parse().unwrap();
+    let time = labelled_row(input, "Time")
+        .replace(' ', "")
+        .parse()
+        .unwrap();
+    let distance = labelled_row(input, "Distance")
+        .replace(' ', "")
+        .parse()
+        .unwrap();
 
     num_winning_combos(time, distance)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{num_winning_combos, optimal_hold, part_1, part_2, per_race_counts, solve_races};
+    use crate::test_util::Lcg;
+
+    const SAMPLE: &str = "Time:      7  15   30
+Distance:  9  40  200";
+
+    #[test]
+    fn test_part_1() {
+        assert_eq!(part_1(SAMPLE), 288)
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(part_2(SAMPLE), 71503)
+    }
+
+    #[test]
+    fn test_rows_in_any_order() {
+        let input = "Distance:  9  40  200
+Time:      7  15   30";
+        assert_eq!(part_1(input), 288);
+        assert_eq!(part_2(input), 71503);
+    }
+
+    #[test]
+    fn test_per_race_counts() {
+        assert_eq!(per_race_counts(SAMPLE), [4, 8, 9])
+    }
+
+    #[test]
+    fn test_solve_races() {
+        assert_eq!(solve_races(&[(7, 9), (15, 40), (30, 200)]), 288)
+    }
+
+    #[test]
+    fn test_optimal_hold() {
+        assert_eq!(optimal_hold(7), Some((3, 12)));
+        assert_eq!(optimal_hold(30), Some((15, 225)));
+        assert_eq!(
+            optimal_hold(6_000_000_000),
+            Some((3_000_000_000, 9_000_000_000_000_000_000))
+        );
+        assert_eq!(optimal_hold(i64::MAX), None);
+    }
+
+    #[test]
+    fn test_no_overflow_for_huge_race() {
+        // Only a wait time of 1 or `time - 1` fails to beat the record
+        assert_eq!(num_winning_combos(i64::MAX, i64::MAX - 1), i64::MAX - 3)
+    }
+
+    /// Count the winning wait times by trying every one of them
+    fn brute_force_winning_combos(time: i64, distance: i64) -> i64 {
+        (0..=time)
+            .filter(|wait_time| wait_time * (time - wait_time) > distance)
+            .count() as i64
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Lcg::new(12345);
+
+        for _ in 0..1000 {
+            let time = rng.below(100) as i64;
+            // Go a little past the best possible distance, so that some races
+            // can't be won
+            let distance = rng.below((time * time / 4 + 3) as usize) as i64;
+            assert_eq!(
+                num_winning_combos(time, distance),
+                brute_force_winning_combos(time, distance),
+                "time {time}, distance {distance}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tied_record_doesnt_win() {
+        // Waiting 5ms exactly ties the record
+        assert_eq!(num_winning_combos(10, 25), 0);
+        assert_eq!(brute_force_winning_combos(10, 25), 0);
+        // Waiting 2ms or 5ms ties the record, leaving only 3ms and 4ms
+        assert_eq!(num_winning_combos(7, 10), 2);
+        assert_eq!(brute_force_winning_combos(7, 10), 2);
+        // Waiting 5ms or 6ms ties the record
+        assert_eq!(num_winning_combos(11, 30), 0);
+    }
+}
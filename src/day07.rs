@@ -0,0 +1,118 @@
+//! Parsing shared between both parts of day 7, which only differ in how the
+//! cards are ranked
+
+use std::fmt::Display;
+
+/// A reason why a line of the input isn't a valid hand and bid
+#[derive(Debug, PartialEq, Eq)]
+pub enum HandError {
+    InvalidCard(char),
+    WrongLength(usize),
+    MissingBid,
+    InvalidBid(String),
+}
+
+impl Display for HandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandError::InvalidCard(c) => write!(f, "Invalid card {c:?}"),
+            HandError::WrongLength(n) => write!(f, "Expected 5 cards but found {n}"),
+            HandError::MissingBid => write!(f, "Missing bid"),
+            HandError::InvalidBid(bid) => write!(f, "Invalid bid {bid:?}"),
+        }
+    }
+}
+
+/// Parse the five cards of a hand, giving an error describing the first
+/// invalid card, or the hand having the wrong number of cards
+pub fn try_parse_cards<C: TryFrom<char, Error = char>>(value: &str) -> Result<[C; 5], HandError> {
+    let cards = value
+        .chars()
+        .map(C::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(HandError::InvalidCard)?;
+    let num_cards = cards.len();
+    cards
+        .try_into()
+        .map_err(|_| HandError::WrongLength(num_cards))
+}
+
+/// Parse every hand and bid in the input using `parse_hand`, giving an error
+/// describing the first invalid line rather than panicking
+pub fn try_parse_hands<H>(
+    input: &str,
+    parse_hand: impl Fn(&str) -> Result<H, HandError>,
+) -> Result<Vec<(H, usize)>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let parse_line = || {
+                let (h, bid) = line.split_once(' ').ok_or(HandError::MissingBid)?;
+                let hand = parse_hand(h)?;
+                let bid = bid
+                    .parse()
+                    .map_err(|_| HandError::InvalidBid(bid.to_owned()))?;
+                Ok((hand, bid))
+            };
+            parse_line().map_err(|e: HandError| format!("{e} on line {}: {line:?}", i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{try_parse_cards, try_parse_hands, HandError};
+    use crate::day07_part_1::Card;
+
+    fn parse(input: &str) -> Result<Vec<([Card; 5], usize)>, String> {
+        try_parse_hands(input, try_parse_cards)
+    }
+
+    #[test]
+    fn test_parse_hands() {
+        assert_eq!(
+            parse("32T3K 765\nAAAAA 684"),
+            Ok(vec![
+                ([Card::N3, Card::N2, Card::T, Card::N3, Card::K], 765),
+                ([Card::A; 5], 684)
+            ])
+        )
+    }
+
+    #[test]
+    fn test_invalid_card() {
+        assert_eq!(
+            try_parse_cards::<Card>("T5XJ5"),
+            Err(HandError::InvalidCard('X'))
+        );
+        assert_eq!(
+            parse("32T3K 765\nT5XJ5 684"),
+            Err("Invalid card 'X' on line 2: \"T5XJ5 684\"".to_owned())
+        )
+    }
+
+    #[test]
+    fn test_invalid_hand_length() {
+        assert_eq!(
+            parse("32T3K 765\nT55J 684"),
+            Err("Expected 5 cards but found 4 on line 2: \"T55J 684\"".to_owned())
+        )
+    }
+
+    #[test]
+    fn test_missing_bid() {
+        assert_eq!(
+            parse("32T3K 765\nT55J5"),
+            Err("Missing bid on line 2: \"T55J5\"".to_owned())
+        )
+    }
+
+    #[test]
+    fn test_invalid_bid() {
+        assert_eq!(
+            parse("32T3K 765\nT55J5 68x"),
+            Err("Invalid bid \"68x\" on line 2: \"T55J5 68x\"".to_owned())
+        )
+    }
+}
@@ -2,8 +2,10 @@ use std::cmp::Ordering;
 
 use itertools::Itertools;
 
+use crate::day07::{try_parse_cards, HandError};
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-enum Card {
+pub enum Card {
     N2,
     N3,
     N4,
@@ -19,9 +21,11 @@ enum Card {
     A,
 }
 
-impl From<char> for Card {
-    fn from(value: char) -> Self {
-        match value {
+impl TryFrom<char> for Card {
+    type Error = char;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value {
             'A' => Card::A,
             'K' => Card::K,
             'Q' => Card::Q,
@@ -35,8 +39,8 @@ impl From<char> for Card {
             '4' => Card::N4,
             '3' => Card::N3,
             '2' => Card::N2,
-            _ => panic!(),
-        }
+            _ => return Err(value),
+        })
     }
 }
 
@@ -52,7 +56,7 @@ enum HandType {
 }
 
 #[derive(Debug)]
-struct Hand([Card; 5]);
+pub struct Hand([Card; 5]);
 
 // impl Hand {
 //     fn get_hand_type(&self) -> HandType {
@@ -99,38 +103,44 @@ struct Hand([Card; 5]);
 //     }
 // }
 
+/// Sizes of each group of matching cards in the hand, smallest first
+pub fn card_histogram(cards: &[Card; 5]) -> Vec<usize> {
+    cards
+        .iter()
+        .sorted()
+        .group_by(|v| *v)
+        .into_iter()
+        .map(|(_, v)| v.count())
+        .sorted()
+        .collect_vec()
+}
+
 impl From<&Hand> for HandType {
     fn from(value: &Hand) -> Self {
-        match value.0.iter()
-            .sorted()
-            .group_by(|v| *v)
-            .into_iter()
-            .map(|(_, v)| v.count())
-            .sorted()
-            .collect_vec()[..] {
-                [5] => HandType::FiveOfAKind,
-                [1, 4] => HandType::FourOfAKind,
-                [2, 3] => HandType::FullHouse,
-                [1, 1, 3] => HandType::ThreeOfAKind,
-                [1, 2, 2] => HandType::TwoPair,
-                [1, 1, 1, 2] => HandType::OnePair,
-                [1, 1, 1, 1, 1] => HandType::HighCard,
-                _ => panic!()
-            }
+        match card_histogram(&value.0)[..] {
+            [5] => HandType::FiveOfAKind,
+            [1, 4] => HandType::FourOfAKind,
+            [2, 3] => HandType::FullHouse,
+            [1, 1, 3] => HandType::ThreeOfAKind,
+            [1, 2, 2] => HandType::TwoPair,
+            [1, 1, 1, 2] => HandType::OnePair,
+            [1, 1, 1, 1, 1] => HandType::HighCard,
+            _ => panic!(),
+        }
+    }
+}
+
+impl Hand {
+    /// Parse a hand, giving an error describing the first invalid card, or
+    /// the hand having the wrong number of cards
+    fn try_new(value: &str) -> Result<Self, HandError> {
+        try_parse_cards(value).map(Hand)
     }
 }
 
 impl From<&str> for Hand {
     fn from(value: &str) -> Self {
-        assert_eq!(value.len(), 5);
-        Hand(
-            value
-                .chars()
-                .map_into::<Card>()
-                .collect_vec()
-                .try_into()
-                .unwrap(),
-        )
+        Hand::try_new(value).unwrap_or_else(|e| panic!("{e}"))
     }
 }
 
@@ -157,20 +167,33 @@ impl Ord for Hand {
     }
 }
 
-fn parse_hand(line: &str) -> (Hand, usize) {
-    let (h, bid) = line.split_once(' ').unwrap();
-    (Hand::from(h), bid.parse().unwrap())
+/// Parse every hand in the input, giving an error describing the first
+/// invalid line rather than panicking
+fn try_parse_hands(input: &str) -> Result<Vec<(Hand, usize)>, String> {
+    crate::day07::try_parse_hands(input, Hand::try_new)
 }
 
-#[aoc(day7, part1)]
-pub fn part_1(input: &str) -> usize {
-    input
-        .lines()
-        .map(parse_hand)
+/// Calculate each hand's `rank * bid` contribution to the winnings, from the
+/// lowest ranked hand to the highest, giving an error if any hand contains an
+/// invalid card
+pub fn try_winning_contributions(input: &str) -> Result<Vec<usize>, String> {
+    Ok(try_parse_hands(input)?
+        .into_iter()
         .sorted()
         .enumerate()
         .map(|(i, (_, bid))| (i + 1) * bid)
-        .sum()
+        .collect())
+}
+
+/// Rank the hands and calculate the total winnings, giving an error if any
+/// hand contains an invalid card
+pub fn try_rank_hands(input: &str) -> Result<usize, String> {
+    Ok(try_winning_contributions(input)?.into_iter().sum())
+}
+
+#[aoc(day7, part1)]
+pub fn part_1(input: &str) -> usize {
+    try_rank_hands(input).unwrap()
 }
 
 // #[aoc(day7, part2)]
@@ -180,10 +203,32 @@ pub fn part_1(input: &str) -> usize {
 
 #[cfg(test)]
 mod test {
-    // use crate::day1::clean_up_line_part_2;
+    use crate::day07_part_1::{card_histogram, part_1, Hand, HandType};
+    use crate::test_util::assert_same_type_ordered_by_cards;
+
+    #[test]
+    fn test_part_1() {
+        assert_eq!(
+            part_1(
+                "32T3K 765\n\
+                T55J5 684\n\
+                KK677 28\n\
+                KTJJT 220\n\
+                QQQJA 483"
+            ),
+            6440
+        )
+    }
 
-    // #[test]
-    // fn test_consume_strings() {
-    //     assert_eq!(clean_up_line_part_2("two1nine"), 29)
-    // }
+    #[test]
+    fn test_card_histogram() {
+        assert_eq!(card_histogram(&Hand::from("QQQAA").0), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_same_type_ordered_by_cards() {
+        assert_same_type_ordered_by_cards::<Hand>(b"23456789TJQKA", "23456789TJQKA", |a, b| {
+            HandType::from(a) == HandType::from(b)
+        });
+    }
 }
@@ -1,9 +1,11 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::cmp::Ordering;
 
 use itertools::Itertools;
 
+use crate::day07::{try_parse_cards, HandError};
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-enum Card {
+pub enum Card {
     J,
     N2,
     N3,
@@ -19,9 +21,11 @@ enum Card {
     A,
 }
 
-impl From<char> for Card {
-    fn from(value: char) -> Self {
-        match value {
+impl TryFrom<char> for Card {
+    type Error = char;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value {
             'A' => Card::A,
             'K' => Card::K,
             'Q' => Card::Q,
@@ -35,7 +39,19 @@ impl From<char> for Card {
             '4' => Card::N4,
             '3' => Card::N3,
             '2' => Card::N2,
-            x => panic!("Unknown card type {x}"),
+            _ => return Err(value),
+        })
+    }
+}
+
+impl Card {
+    /// The strength of the card when `J` is a jack, which ranks between `T`
+    /// and `Q`, rather than a joker, which is the weakest card
+    fn jack_strength(self) -> u8 {
+        match self {
+            Card::J => Card::T as u8 + 1,
+            card if card > Card::T => card as u8 + 1,
+            card => card as u8,
         }
     }
 }
@@ -51,108 +67,75 @@ enum HandType {
     FiveOfAKind,
 }
 
-impl From<&Hand> for HandType {
-    fn from(value: &Hand) -> Self {
-        let mut card_counts: HashMap<Card, u8> = HashMap::default();
+/// Sizes of each group of matching cards in the hand, smallest first
+///
+/// Jokers are counted as their own group here.
+pub fn card_histogram(cards: &[Card; 5]) -> Vec<usize> {
+    cards
+        .iter()
+        .sorted()
+        .group_by(|v| *v)
+        .into_iter()
+        .map(|(_, v)| v.count())
+        .sorted()
+        .collect_vec()
+}
 
-        let mut num_jokers = 0;
+impl HandType {
+    /// Find the type of the hand, treating `J` as a joker if `jokers` is set,
+    /// or as a jack otherwise
+    fn new(cards: &[Card; 5], jokers: bool) -> Self {
+        let num_jokers = cards.iter().filter(|&&c| c == Card::J).count();
+        let mut histogram = card_histogram(cards);
 
-        for card in value.0 {
-            if let Card::J = card {
-                num_jokers += 1;
-            }
-            card_counts.insert(card, card_counts.get(&card).unwrap_or(&0) + 1);
+        // Jokers always join the largest other group
+        if jokers && num_jokers > 0 && num_jokers < 5 {
+            let joker_group = histogram.iter().position(|&n| n == num_jokers).unwrap();
+            histogram.remove(joker_group);
+            *histogram.last_mut().unwrap() += num_jokers;
         }
 
-        let mut num_pairs = 0;
-        let mut num_triples = 0;
-
-        for (card, count) in card_counts {
-            match count {
-                1 => {
-                    if num_jokers == 4 {
-                        return HandType::FiveOfAKind;
-                    }
-                }
-                2 => {
-                    if num_jokers == 3 {
-                        return HandType::FiveOfAKind;
-                    }
-                    if num_jokers == 2 && card != Card::J {
-                        return HandType::FourOfAKind;
-                    }
-                    num_pairs += 1;
-                }
-                3 => {
-                    if num_jokers == 2 {
-                        return HandType::FiveOfAKind;
-                    }
-                    if num_jokers == 1 {
-                        return HandType::FourOfAKind;
-                    }
-                    num_triples += 1;
-                }
-                4 => {
-                    if num_jokers == 1 || num_jokers == 4 {
-                        return HandType::FiveOfAKind;
-                    }
-                    return HandType::FourOfAKind;
-                }
-                5 => {
-                    return HandType::FiveOfAKind;
-                }
-                _ => {}
-            }
+        match histogram[..] {
+            [5] => HandType::FiveOfAKind,
+            [1, 4] => HandType::FourOfAKind,
+            [2, 3] => HandType::FullHouse,
+            [1, 1, 3] => HandType::ThreeOfAKind,
+            [1, 2, 2] => HandType::TwoPair,
+            [1, 1, 1, 2] => HandType::OnePair,
+            [1, 1, 1, 1, 1] => HandType::HighCard,
+            _ => panic!(),
         }
+    }
+}
 
-        if num_pairs == 2 {
-            match num_jokers {
-                1 => HandType::FullHouse,
-                2 => HandType::FourOfAKind,
-                0 => HandType::TwoPair,
-                _ => panic!(),
-            }
-        } else if num_pairs == 1 {
-            if num_triples == 1 {
-                HandType::FullHouse
-            } else {
-                match num_jokers {
-                    0 => HandType::OnePair,
-                    1 => HandType::ThreeOfAKind,
-                    // 2 jokers are the only pair - match them with another random card
-                    2 => HandType::ThreeOfAKind,
-                    3 => panic!("Should be five of a kind"),
-                    _ => panic!(),
-                }
-            }
-        } else if num_triples == 1 {
-            if num_jokers == 3 {
-                HandType::FourOfAKind
-            } else {
-                HandType::ThreeOfAKind
-            }
-        } else if num_jokers == 1 {
-            HandType::OnePair
-        } else {
-            HandType::HighCard
-        }
+impl From<&Hand> for HandType {
+    fn from(value: &Hand) -> Self {
+        HandType::new(&value.0, true)
     }
 }
 
 #[derive(Debug)]
-struct Hand([Card; 5]);
+pub struct Hand([Card; 5]);
+
+impl Hand {
+    /// The key to sort the hand by when `J` is a jack rather than a joker
+    fn jack_key(&self) -> (HandType, [u8; 5]) {
+        (
+            HandType::new(&self.0, false),
+            self.0.map(Card::jack_strength),
+        )
+    }
+
+    /// Parse a hand, giving an error describing the first invalid card, or
+    /// the hand having the wrong number of cards
+    fn try_new(value: &str) -> Result<Self, HandError> {
+        try_parse_cards(value).map(Hand)
+    }
+}
 
 impl From<&str> for Hand {
     fn from(value: &str) -> Self {
-        assert_eq!(value.len(), 5);
-        Hand(
-            value
-                .chars()
-                .map_into::<Card>()
-                .collect_vec()
-                .try_into()
-                .expect("Vec of wrong size?"),
-        )
+        Hand::try_new(value).unwrap_or_else(|e| panic!("{e}"))
     }
 }
 
@@ -179,38 +162,141 @@ impl Ord for Hand {
     }
 }
 
-fn parse_hand(line: &str) -> (Hand, usize) {
-    let (h, bid) = line.split_once(' ').unwrap();
-    (Hand::from(h), bid.parse().unwrap())
+/// Parse every hand in the input, giving an error describing the first
+/// invalid line rather than panicking
+fn try_parse_hands(input: &str) -> Result<Vec<(Hand, usize)>, String> {
+    crate::day07::try_parse_hands(input, Hand::try_new)
 }
 
-#[aoc(day7, part2)]
-pub fn part_2(input: &str) -> usize {
-    input
-        .lines()
-        .map(parse_hand)
-        .sorted()
+/// Calculate each hand's `rank * bid` contribution to the winnings, from the
+/// lowest ranked hand to the highest, treating `J` as a joker if `jokers` is
+/// set, or as a jack otherwise
+fn ranked_contributions(hands: impl Iterator<Item = (Hand, usize)>, jokers: bool) -> Vec<usize> {
+    let ranked = if jokers {
+        hands.sorted().collect_vec()
+    } else {
+        hands
+            .sorted_by_cached_key(|(hand, bid)| (hand.jack_key(), *bid))
+            .collect_vec()
+    };
+    ranked
+        .into_iter()
         .enumerate()
         .map(|(i, (_, bid))| (i + 1) * bid)
-        .sum()
+        .collect()
+}
+
+/// Calculate the total winnings for the given hands and their bids, treating
+/// `J` as a joker if `jokers` is set, or as a jack otherwise
+///
+/// Ranking the hands requires sorting them, so they still get collected
+/// internally, but this lets callers parse the hands lazily
+pub fn total_winnings(hands: impl Iterator<Item = (Hand, usize)>, jokers: bool) -> usize {
+    ranked_contributions(hands, jokers).into_iter().sum()
+}
+
+/// Calculate each hand's contribution to the winnings in rank order, treating
+/// `J` as a joker if `jokers` is set, or as a jack otherwise
+///
+/// Panics if any line isn't a valid hand and bid, as described by the error
+/// from `try_rank_hands`.
+pub fn winning_contributions(input: &str, jokers: bool) -> Vec<usize> {
+    ranked_contributions(try_parse_hands(input).unwrap().into_iter(), jokers)
+}
+
+/// Rank the hands and calculate the total winnings, giving an error if any
+/// hand contains an invalid card
+pub fn try_rank_hands(input: &str) -> Result<usize, String> {
+    Ok(total_winnings(try_parse_hands(input)?.into_iter(), true))
+}
+
+/// Parse a list of hands without bids, one per line, and sort them from
+/// weakest to strongest, treating `J` as a joker if `jokers` is set, or as a
+/// jack otherwise
+///
+/// Panics if any line isn't a valid hand of five cards.
+pub fn rank_only(input: &str, jokers: bool) -> Vec<Hand> {
+    let hands = input.lines().map(str::trim).map(Hand::from);
+    if jokers {
+        hands.sorted().collect_vec()
+    } else {
+        hands.sorted_by_cached_key(Hand::jack_key).collect_vec()
+    }
+}
+
+#[aoc(day7, part2)]
+pub fn part_2(input: &str) -> usize {
+    try_rank_hands(input).unwrap()
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day07_part_2::{part_2, Hand, HandType};
+    use crate::day07_part_2::{
+        card_histogram, part_2, rank_only, total_winnings, try_parse_hands, winning_contributions,
+        Hand, HandType,
+    };
+    use crate::test_util::assert_same_type_ordered_by_cards;
+
+    const SAMPLE: &str = "32T3K 765\n\
+        T55J5 684\n\
+        KK677 28\n\
+        KTJJT 220\n\
+        QQQJA 483";
 
     #[test]
     fn test_part_2() {
+        assert_eq!(part_2(SAMPLE), 5905)
+    }
+
+    #[test]
+    fn test_total_winnings() {
+        let hands = try_parse_hands(SAMPLE).unwrap().into_iter();
+        assert_eq!(total_winnings(hands, true), 5905);
+        let hands = try_parse_hands(SAMPLE).unwrap().into_iter();
+        assert_eq!(total_winnings(hands, false), 6440)
+    }
+
+    #[test]
+    fn test_winning_contributions() {
+        let contributions = winning_contributions(SAMPLE, true);
+        assert_eq!(contributions.iter().sum::<usize>(), 5905);
+        // KTJJT is the strongest hand once jokers are wild
+        assert_eq!(contributions.last(), Some(&(5 * 220)));
         assert_eq!(
-            part_2(
-                "32T3K 765\n\
-                T55J5 684\n\
-                KK677 28\n\
-                KTJJT 220\n\
-                QQQJA 483"
-            ),
-            5905,
-        )
+            winning_contributions(SAMPLE, false).iter().sum::<usize>(),
+            6440
+        );
+    }
+
+    #[test]
+    fn test_rank_only() {
+        let hands = "32T3K\n\
+            T55J5\n\
+            KK677\n\
+            KTJJT\n\
+            QQQJA";
+        assert_eq!(
+            rank_only(hands, true),
+            ["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"].map(Hand::from)
+        );
+        assert_eq!(
+            rank_only(hands, false),
+            ["32T3K", "KTJJT", "KK677", "T55J5", "QQQJA"].map(Hand::from)
+        );
+    }
+
+    #[test]
+    fn test_card_histogram() {
+        assert_eq!(card_histogram(&Hand::from("QQQAA").0), vec![2, 3]);
+        assert_eq!(card_histogram(&Hand::from("QQJJA").0), vec![1, 2, 2]);
+    }
+
+    #[test]
+    fn test_jack_key() {
+        assert!(Hand::from("JJJJJ").jack_key() > Hand::from("TTTTT").jack_key());
+        assert!(Hand::from("JJJJJ").jack_key() < Hand::from("QQQQQ").jack_key());
+        // Without jokers, a jack doesn't join the other groups
+        assert!(Hand::from("QQQJA").jack_key() < Hand::from("22233").jack_key());
     }
 
     #[test]
@@ -245,4 +331,12 @@ mod test {
         assert!(Hand::from("JKKK2") < Hand::from("QQQQ2"));
         assert!(Hand::from("J2345") < Hand::from("22456"));
     }
+
+    #[test]
+    fn test_same_type_ordered_by_cards() {
+        // Jokers are drawn more often so that plenty of hands contain them
+        assert_same_type_ordered_by_cards::<Hand>(b"23456789TJQKAJJJ", "J23456789TQKA", |a, b| {
+            HandType::from(a) == HandType::from(b)
+        });
+    }
 }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use itertools::Itertools;
+
 fn read_graph_entry(line: &str) -> (String, (String, String)) {
     let (start, end) = line.split_once(" = ").unwrap();
 
@@ -10,8 +12,8 @@ fn read_graph_entry(line: &str) -> (String, (String, String)) {
     (start.to_owned(), (left.to_owned(), right.to_owned()))
 }
 
-#[aoc(day8, part1)]
-pub fn part_1(input: &str) -> i64 {
+/// Read the directions and the graph from the input
+fn read_input(input: &str) -> (&str, HashMap<String, (String, String)>) {
     let mut lines = input.lines();
     let directions = lines.next().unwrap();
 
@@ -24,92 +26,231 @@ pub fn part_1(input: &str) -> i64 {
         graph.insert(k, v);
     }
 
-    let mut position = "AAA".to_owned();
-    let mut steps = 0;
+    (directions, graph)
+}
 
-    loop {
-        for direction in directions.chars() {
-            position = match direction {
-                'L' => graph[&position].0.clone(),
-                'R' => graph[&position].1.clone(),
-                _ => panic!(),
-            };
-            steps += 1;
-            if position == "ZZZ" {
-                return steps;
-            }
+/// The network of nodes, with each node's name interned as an index
+struct Network {
+    directions: String,
+    /// Names of each node, sorted so that they can be looked up by binary
+    /// search
+    names: Vec<String>,
+    /// Left and right connections of each node
+    edges: Vec<(usize, usize)>,
+}
+
+impl Network {
+    fn new(input: &str) -> Self {
+        let (directions, graph) = read_input(input);
+
+        let names = graph.keys().cloned().sorted().collect_vec();
+        let edges = names
+            .iter()
+            .map(|name| {
+                let (left, right) = &graph[name];
+                (
+                    names.binary_search(left).unwrap(),
+                    names.binary_search(right).unwrap(),
+                )
+            })
+            .collect_vec();
+
+        Network {
+            directions: directions.to_owned(),
+            names,
+            edges,
         }
     }
-}
 
-fn get_num_steps_to_end(
-    graph: &HashMap<String, (String, String)>,
-    directions: &str,
-    start: &str,
-) -> i64 {
-    let mut position = start.to_owned();
-
-    let mut steps = 0;
-    loop {
-        for direction in directions.chars() {
-            position = match direction {
-                'L' => graph[&position].0.clone(),
-                'R' => graph[&position].1.clone(),
-                _ => panic!(),
-            };
+    fn id(&self, name: &str) -> usize {
+        self.names
+            .binary_search_by(|n| n.as_str().cmp(name))
+            .unwrap_or_else(|_| panic!("No node named {name}"))
+    }
+
+    /// Return the node reached by moving in the given direction
+    fn step(&self, node: usize, direction: char) -> usize {
+        match direction {
+            'L' => self.edges[node].0,
+            'R' => self.edges[node].1,
+            _ => panic!(),
+        }
+    }
+
+    /// Number of steps taken from `start` until reaching a node whose name
+    /// matches `is_goal`, which is 0 if the start is already a goal
+    fn steps_until(&self, start: usize, is_goal: impl Fn(&str) -> bool) -> i64 {
+        let mut position = start;
+        let mut steps = 0;
+        let mut directions = self.directions.chars().cycle();
+
+        while !is_goal(&self.names[position]) {
+            position = self.step(position, directions.next().unwrap());
             steps += 1;
-            if position.ends_with('Z') {
-                return steps;
-            }
         }
+
+        steps
+    }
+
+    /// Number of steps to walk from `AAA` to `ZZZ`
+    fn steps_simple(&self) -> i64 {
+        self.steps_until(self.id("AAA"), |name| name == "ZZZ")
+    }
+
+    /// Number of steps for a ghost starting at `start` to reach a node ending
+    /// in `Z`, which is the period at which it keeps reaching its goal
+    fn cycle_length(&self, start: usize) -> i64 {
+        self.steps_until(start, |name| name.ends_with('Z'))
+    }
+
+    /// Number of steps for each ghost, starting at each node ending in `A`, to
+    /// reach a node ending in `Z`
+    fn ghost_steps(&self) -> Vec<i64> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.ends_with('A'))
+            .map(|(id, _)| self.cycle_length(id))
+            .collect_vec()
+    }
+
+    /// Number of steps for all the ghosts, starting at every node ending in
+    /// `A`, to simultaneously reach nodes ending in `Z`
+    fn steps_ghost(&self) -> i64 {
+        self.ghost_steps()
+            .into_iter()
+            .reduce(num::integer::lcm)
+            .unwrap()
     }
 }
 
-#[aoc(day8, part2)]
-pub fn part_2(input: &str) -> i64 {
-    let mut lines = input.lines();
-    let directions = lines.next().unwrap();
+#[aoc(day8, part1)]
+pub fn part_1(input: &str) -> i64 {
+    Network::new(input).steps_simple()
+}
 
-    // Discard empty line
-    lines.next().unwrap();
+/// Returns the names of the nodes visited when walking from `start` until a
+/// node ending in `Z` is reached, including both the start and the goal.
+///
+/// If no goal is reached before the walk starts repeating itself, the path
+/// is returned up until that point.
+pub fn path_to_goal(input: &str, start: &str) -> Vec<String> {
+    let network = Network::new(input);
 
-    let mut graph: HashMap<String, (String, String)> = HashMap::new();
-    let mut starting_positions = vec![];
-    for line in lines {
-        let (k, v) = read_graph_entry(line);
-        graph.insert(k.clone(), v);
-        if k.ends_with('A') {
-            starting_positions.push(k);
+    let mut position = network.id(start);
+    let mut path = vec![start.to_owned()];
+
+    // After this many steps, we must have visited some (node, direction)
+    // pair twice, meaning we're stuck in a cycle with no goal
+    let max_steps = network.names.len() * network.directions.len();
+
+    for direction in network.directions.chars().cycle().take(max_steps) {
+        if network.names[position].ends_with('Z') {
+            break;
         }
+        position = network.step(position, direction);
+        path.push(network.names[position].clone());
     }
 
-    starting_positions
-        .iter()
-        .map(|pos| get_num_steps_to_end(&graph, directions, pos))
-        .reduce(num::integer::lcm)
-        .unwrap()
+    path
+}
+
+/// Returns the number of ghosts, along with the number of steps each one
+/// takes to reach its goal on its own
+pub fn ghost_summary(input: &str) -> (usize, Vec<i64>) {
+    let steps = Network::new(input).ghost_steps();
+    (steps.len(), steps)
+}
+
+/// Returns the number of steps a ghost starting at `start` takes to reach its
+/// goal, which is the period that `part_2` combines for all the ghosts
+///
+/// A ghost that starts on a goal takes 0 steps.
+pub fn cycle_length(input: &str, start: &str) -> i64 {
+    let network = Network::new(input);
+    network.cycle_length(network.id(start))
+}
+
+#[aoc(day8, part2)]
+pub fn part_2(input: &str) -> i64 {
+    Network::new(input).steps_ghost()
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day08::part_2;
+    use crate::day08::{cycle_length, ghost_summary, part_1, part_2, path_to_goal, Network};
+
+    const SAMPLE_PART_2: &str = "LR\n\
+\n\
+        11A = (11B, XXX)\n\
+        11B = (XXX, 11Z)\n\
+        11Z = (11B, XXX)\n\
+        22A = (22B, XXX)\n\
+        22B = (22C, 22C)\n\
+        22C = (22Z, 22Z)\n\
+        22Z = (22B, 22B)\n\
+        XXX = (XXX, XXX)";
 
     #[test]
     fn test_part_2() {
+        assert_eq!(part_2(SAMPLE_PART_2), 6)
+    }
+
+    #[test]
+    fn test_part_1() {
         assert_eq!(
-            part_2(
-                "LR\n\
-\n\
-                11A = (11B, XXX)\n\
-                11B = (XXX, 11Z)\n\
-                11Z = (11B, XXX)\n\
-                22A = (22B, XXX)\n\
-                22B = (22C, 22C)\n\
-                22C = (22Z, 22Z)\n\
-                22Z = (22B, 22B)\n\
-                XXX = (XXX, XXX)"
+            part_1(
+                "LLR\n\
+                \n\
+                AAA = (BBB, BBB)\n\
+                BBB = (AAA, ZZZ)\n\
+                ZZZ = (ZZZ, ZZZ)"
             ),
             6
         )
     }
+
+    #[test]
+    fn test_network_ghost() {
+        let network = Network::new(SAMPLE_PART_2);
+        assert_eq!(network.steps_ghost(), 6);
+    }
+
+    #[test]
+    fn test_ghost_summary() {
+        assert_eq!(ghost_summary(SAMPLE_PART_2), (2, vec![2, 3]));
+    }
+
+    #[test]
+    fn test_cycle_length() {
+        assert_eq!(cycle_length(SAMPLE_PART_2, "11A"), 2);
+        assert_eq!(
+            part_2(SAMPLE_PART_2) % cycle_length(SAMPLE_PART_2, "11A"),
+            0
+        );
+        assert_eq!(
+            part_2(SAMPLE_PART_2) % cycle_length(SAMPLE_PART_2, "22A"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_start_is_goal() {
+        let input = "LR\n\
+            \n\
+            AAZ = (BBB, BBB)\n\
+            BBB = (AAZ, AAZ)";
+        assert_eq!(cycle_length(input, "AAZ"), 0);
+        assert_eq!(cycle_length(input, "BBB"), 1);
+        assert_eq!(path_to_goal(input, "AAZ"), ["AAZ"]);
+    }
+
+    #[test]
+    fn test_path_to_goal() {
+        assert_eq!(path_to_goal(SAMPLE_PART_2, "11A"), ["11A", "11B", "11Z"]);
+        assert_eq!(
+            path_to_goal(SAMPLE_PART_2, "22A"),
+            ["22A", "22B", "22C", "22Z"]
+        );
+    }
 }
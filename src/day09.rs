@@ -1,58 +1,208 @@
 use itertools::Itertools;
 
-fn read_line(line: &str) -> Vec<i64> {
-    line.split(' ').map(|n| n.parse().unwrap()).collect_vec()
+/// Read a sequence of numbers, separated by whitespace and/or commas
+///
+/// The numbers are widened to `i128`, so that the differences of any values
+/// that fit in an `i64` can be taken
+fn read_line(line: &str) -> Vec<i128> {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().unwrap())
+        .collect_vec()
 }
 
-fn extrapolate_value(values: Vec<i64>) -> i64 {
-    let simplified_values = values
+/// Take the differences between each pair of consecutive values, giving their
+/// sum too, or `None` if anything overflows
+///
+/// Each level of differences can double the size of the values, so even
+/// `i128` overflows for long enough sequences.
+fn differences(values: &[i128]) -> Option<(Vec<i128>, i128)> {
+    let differences = values
         .iter()
-        .tuple_windows::<(_, _)>()
-        .map(|(a, b)| b - a)
-        .collect_vec();
+        .tuple_windows()
+        .map(|(a, b)| b.checked_sub(*a))
+        .collect::<Option<Vec<_>>>()?;
+    let sum = differences
+        .iter()
+        .try_fold(0i128, |sum, &d| sum.checked_add(d))?;
+    Some((differences, sum))
+}
+
+fn extrapolate_value(values: Vec<i128>) -> Option<i128> {
+    // A lone value can only be continued as a constant sequence
+    if values.len() == 1 {
+        return Some(values[0]);
+    }
 
-    if simplified_values.iter().sum::<i64>() == 0 {
-        values[0]
+    let (simplified_values, sum) = differences(&values)?;
+
+    if sum == 0 {
+        Some(values[0])
     } else {
-        values[values.len() - 1] + extrapolate_value(simplified_values)
+        values[values.len() - 1].checked_add(extrapolate_value(simplified_values)?)
     }
 }
 
-fn extrapolate_value_backwards(values: Vec<i64>) -> i64 {
-    let simplified_values = values
-        .iter()
-        .tuple_windows::<(_, _)>()
-        .map(|(a, b)| b - a)
-        .collect_vec();
+fn extrapolate_value_backwards(values: Vec<i128>) -> Option<i128> {
+    // A lone value can only be continued as a constant sequence
+    if values.len() == 1 {
+        return Some(values[0]);
+    }
+
+    let (simplified_values, sum) = differences(&values)?;
 
-    if simplified_values.iter().sum::<i64>() == 0 {
-        values[0]
+    if sum == 0 {
+        Some(values[0])
     } else {
-        values[0] - extrapolate_value_backwards(simplified_values)
+        values[0].checked_sub(extrapolate_value_backwards(simplified_values)?)
     }
 }
 
+/// Extrapolate each sequence in the input in both directions, giving
+/// `(backward, forward)` for each line
+pub fn extrapolations(input: &str) -> Vec<(i64, i64)> {
+    try_extrapolations(input).unwrap()
+}
+
+/// Like `extrapolations`, but give an error rather than panicking if any of
+/// the extrapolated values don't fit in an `i64`, or if the differences get
+/// too large to calculate
+pub fn try_extrapolations(input: &str) -> Result<Vec<(i64, i64)>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let values = read_line(line);
+            let narrow = |value: Option<i128>| {
+                let value = value
+                    .ok_or_else(|| format!("Extrapolating line {} overflows an i128", i + 1))?;
+                i64::try_from(value).map_err(|_| {
+                    format!(
+                        "Extrapolated value {value} on line {} overflows an i64",
+                        i + 1
+                    )
+                })
+            };
+            Ok((
+                narrow(extrapolate_value_backwards(values.clone()))?,
+                narrow(extrapolate_value(values))?,
+            ))
+        })
+        .collect()
+}
+
 #[aoc(day9, part1)]
 pub fn part_1(input: &str) -> i64 {
-    input.lines().map(read_line).map(extrapolate_value).sum()
+    extrapolations(input)
+        .iter()
+        .map(|(_, forward)| forward)
+        .sum()
 }
 
 #[aoc(day9, part2)]
 pub fn part_2(input: &str) -> i64 {
-    input.lines().map(read_line).map(extrapolate_value_backwards).sum()
+    extrapolations(input)
+        .iter()
+        .map(|(backward, _)| backward)
+        .sum()
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day09::{extrapolate_value, extrapolate_value_backwards};
+    use itertools::Itertools;
+
+    use crate::day09::{
+        extrapolate_value, extrapolate_value_backwards, extrapolations, part_1, part_2, read_line,
+        try_extrapolations,
+    };
 
     #[test]
     fn test_extrapolate() {
-        assert_eq!(extrapolate_value(vec![0, 3, 6, 9, 12, 15]), 18)
+        assert_eq!(extrapolate_value(vec![0, 3, 6, 9, 12, 15]), Some(18))
     }
 
     #[test]
     fn test_extrapolate_backwards() {
-        assert_eq!(extrapolate_value_backwards(vec![0, 3, 6, 9, 12, 15]), -3)
+        assert_eq!(
+            extrapolate_value_backwards(vec![0, 3, 6, 9, 12, 15]),
+            Some(-3)
+        )
+    }
+
+    #[test]
+    fn test_read_line_separators() {
+        assert_eq!(read_line("1 2 3"), vec![1, 2, 3]);
+        assert_eq!(read_line("1, 2, 3"), vec![1, 2, 3]);
+        assert_eq!(read_line("1,2,3"), vec![1, 2, 3]);
+        assert_eq!(
+            extrapolate_value(read_line("1, 2, 3")),
+            extrapolate_value(read_line("1 2 3"))
+        );
+    }
+
+    #[test]
+    fn test_extrapolate_single_value() {
+        assert_eq!(extrapolate_value(vec![5]), Some(5));
+        assert_eq!(extrapolate_value_backwards(vec![5]), Some(5));
+    }
+
+    #[test]
+    fn test_extrapolations() {
+        let input = "0 3 6 9 12 15\n\
+            10 13 16 21 30 45";
+        assert_eq!(extrapolations(input), vec![(-3, 18), (5, 68)]);
+        assert_eq!(part_1(input), 18 + 68);
+        assert_eq!(part_2(input), -3 + 5);
+    }
+
+    #[test]
+    fn test_no_overflow_for_large_values() {
+        let line = format!("{} {} {}", -i64::MAX, i64::MAX, i64::MAX);
+        let max = i64::MAX as i128;
+        // The differences overflow an i64, though the next value doesn't
+        assert_eq!(extrapolate_value(read_line(&line)), Some(-max));
+        assert_eq!(
+            extrapolate_value_backwards(read_line(&line)),
+            Some(-5 * max)
+        );
+        assert_eq!(
+            try_extrapolations(&line),
+            Err(format!(
+                "Extrapolated value {} on line 1 overflows an i64",
+                -5 * max
+            ))
+        );
+
+        let line = format!("{} {} {}", i64::MIN + 2, i64::MIN + 1, i64::MIN);
+        assert_eq!(
+            try_extrapolations(&line),
+            Err(format!(
+                "Extrapolated value {} on line 1 overflows an i64",
+                i64::MIN as i128 - 1
+            ))
+        );
+
+        let line = format!("{} {} {}", i64::MAX - 1, i64::MAX - 2, i64::MAX - 3);
+        assert_eq!(
+            try_extrapolations(&line),
+            Ok(vec![(i64::MAX, i64::MAX - 4)])
+        );
+    }
+
+    #[test]
+    fn test_differences_overflow() {
+        // Alternating values double in size with each level of differences.
+        // The first one is nudged so that the differences never sum to 0.
+        let line = (0..70)
+            .map(|i| match i {
+                0 => i64::MAX - 1,
+                i if i % 2 == 0 => i64::MAX,
+                _ => -i64::MAX,
+            })
+            .join(" ");
+        assert_eq!(
+            try_extrapolations(&line),
+            Err("Extrapolating line 1 overflows an i128".to_owned())
+        );
     }
 }
@@ -1,10 +1,13 @@
-use std::ops::{Add, Neg};
+use std::{
+    fmt::Display,
+    ops::{Add, Neg},
+};
 
 use array2d::Array2D;
 use itertools::Itertools;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
+pub enum Direction {
     North,
     South,
     East,
@@ -33,17 +36,19 @@ impl Add<(usize, usize)> for Direction {
     type Output = (usize, usize);
 
     fn add(self, rhs: (usize, usize)) -> Self::Output {
+        // Moving off the top or left of the grid wraps around to a position
+        // that is out of bounds, rather than overflowing
         match self {
-            NORTH => (rhs.0 - 1, rhs.1),
+            NORTH => (rhs.0.wrapping_sub(1), rhs.1),
             SOUTH => (rhs.0 + 1, rhs.1),
             EAST => (rhs.0, rhs.1 + 1),
-            WEST => (rhs.0, rhs.1 - 1),
+            WEST => (rhs.0, rhs.1.wrapping_sub(1)),
         }
     }
 }
 
 #[derive(Debug, Clone)]
-enum Tile {
+pub enum Tile {
     Pipe(Direction, Direction),
     Empty,
     Start,
@@ -65,6 +70,23 @@ impl Tile {
         }
     }
 
+    /// Return the character used to represent this tile in the input
+    fn to_char(&self) -> char {
+        match self {
+            Tile::Pipe(..) => match [NORTH, SOUTH, EAST, WEST].map(|d| self.connects_in_dir(d)) {
+                [true, true, _, _] => '|',
+                [_, _, true, true] => '-',
+                [true, _, true, _] => 'L',
+                [true, _, _, true] => 'J',
+                [_, true, _, true] => '7',
+                [_, true, true, _] => 'F',
+                _ => panic!("Invalid pipe {self:?}"),
+            },
+            Tile::Empty => '.',
+            Tile::Start => 'S',
+        }
+    }
+
     fn get_output_dir(&self, dir: Direction) -> Option<Direction> {
         if let Tile::Pipe(a, b) = self {
             if *a == -dir {
@@ -80,34 +102,84 @@ impl Tile {
     }
 }
 
-impl From<char> for Tile {
-    fn from(value: char) -> Self {
+impl TryFrom<char> for Tile {
+    type Error = char;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
-            '|' => Tile::Pipe(NORTH, SOUTH),
-            '-' => Tile::Pipe(EAST, WEST),
-            'L' => Tile::Pipe(NORTH, EAST),
-            'J' => Tile::Pipe(NORTH, WEST),
-            '7' => Tile::Pipe(SOUTH, WEST),
-            'F' => Tile::Pipe(SOUTH, EAST),
-            '.' => Tile::Empty,
-            'S' => Tile::Start,
-            _ => panic!("Invalid tile"),
+            '|' => Ok(Tile::Pipe(NORTH, SOUTH)),
+            '-' => Ok(Tile::Pipe(EAST, WEST)),
+            'L' => Ok(Tile::Pipe(NORTH, EAST)),
+            'J' => Ok(Tile::Pipe(NORTH, WEST)),
+            '7' => Ok(Tile::Pipe(SOUTH, WEST)),
+            'F' => Ok(Tile::Pipe(SOUTH, EAST)),
+            // Spaces are treated as empty so that padded input still works
+            '.' | ' ' => Ok(Tile::Empty),
+            'S' => Ok(Tile::Start),
+            _ => Err(value),
         }
     }
 }
 
-fn parse_row(line: &str) -> Vec<Tile> {
-    line.chars().map(Tile::from).collect_vec()
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Day10Error {
+    /// A character that isn't a valid tile, and its (row, column)
+    InvalidTile(char, (usize, usize)),
+    /// There is no start tile
+    NoStart,
+    /// There is more than one start tile, at the given positions
+    MultipleStarts(Vec<(usize, usize)>),
+    /// The start isn't part of a closed loop
+    NoLoop,
 }
 
-fn get_start(array: &Array2D<Tile>) -> (usize, usize) {
-    for ((r, c), tile) in array.enumerate_row_major() {
-        if matches!(tile, Tile::Start) {
-            return (r, c);
+impl Display for Day10Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Day10Error::InvalidTile(ch, (r, c)) => {
+                write!(f, "Invalid tile {ch:?} at row {r}, column {c}")
+            }
+            Day10Error::NoStart => write!(f, "No start tile"),
+            Day10Error::MultipleStarts(starts) => write!(f, "Multiple start tiles at {starts:?}"),
+            Day10Error::NoLoop => write!(f, "The start isn't part of a loop"),
         }
     }
+}
 
-    panic!();
+/// Parse the grid, making sure that all the tiles are valid and that there is
+/// exactly one start tile, returning the grid and the start's position
+///
+/// Any rows that are shorter than the others are padded with empty tiles
+fn parse_grid(input: &str) -> Result<(Array2D<Tile>, (usize, usize)), Day10Error> {
+    let mut rows = input
+        .lines()
+        .enumerate()
+        .map(|(r, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(c, ch)| Tile::try_from(ch).map_err(|ch| Day10Error::InvalidTile(ch, (r, c))))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+    for row in &mut rows {
+        row.resize(width, Tile::Empty);
+    }
+
+    let grid = Array2D::from_rows(&rows).unwrap();
+
+    let starts = grid
+        .enumerate_row_major()
+        .filter(|(_, tile)| matches!(tile, Tile::Start))
+        .map(|(pos, _)| pos)
+        .collect_vec();
+
+    match starts.len() {
+        0 => Err(Day10Error::NoStart),
+        1 => Ok((grid, starts[0])),
+        _ => Err(Day10Error::MultipleStarts(starts)),
+    }
 }
 
 /// Traverse until finding the start -- return loop length and other connected
@@ -136,58 +208,189 @@ fn traverse_loop(
     Some((num_steps, direction))
 }
 
-fn find_loop_length(start: &(usize, usize), grid: &Array2D<Tile>) -> (usize, Tile) {
+fn find_loop_length(
+    start: &(usize, usize),
+    grid: &Array2D<Tile>,
+) -> Result<(usize, Tile), Day10Error> {
     // check in each direction
     for direction in [NORTH, EAST, SOUTH, WEST] {
         let result = direction + *start;
         if let Some(cell) = grid.get(result.0, result.1) {
             if cell.connects_in_dir(-direction) {
-                if let Some((loop_len, other_direction)) = traverse_loop(start, direction, grid) {
-                    return (loop_len, Tile::Pipe(direction, other_direction));
+                if let Some((loop_len, final_direction)) = traverse_loop(start, direction, grid) {
+                    // We arrive back at the start travelling in the final
+                    // direction, so the start connects the opposite way
+                    return Ok((loop_len, Tile::Pipe(direction, -final_direction)));
                 }
             }
         }
     }
 
-    panic!("Couldn't find a loop direction")
+    Err(Day10Error::NoLoop)
 }
 
-#[aoc(day10, part1)]
-pub fn part_1(input: &str) -> usize {
-    let grid = Array2D::from_rows(&input.lines().map(parse_row).collect_vec()).unwrap();
+/// Returns every pipe shape that the start could be replaced with to form a
+/// closed loop. There is normally only one, so more than one means that the
+/// input is ambiguous.
+pub fn candidate_start_tiles(input: &str) -> Vec<Tile> {
+    let (grid, start) = parse_grid(input).unwrap();
+
+    [NORTH, EAST, SOUTH, WEST]
+        .into_iter()
+        .filter_map(|direction| {
+            let result = direction + start;
+            let cell = grid.get(result.0, result.1)?;
+            if !cell.connects_in_dir(-direction) {
+                return None;
+            }
+            let (_, final_direction) = traverse_loop(&start, direction, &grid)?;
+            Some(Tile::Pipe(direction, -final_direction))
+        })
+        // Each loop is found once from each end
+        .unique_by(Tile::to_char)
+        .collect_vec()
+}
+
+/// Returns the number of steps from the start to each cell of the main loop,
+/// going whichever way around the loop is shorter. Cells that aren't part of
+/// the loop are `None`.
+pub fn loop_distances(input: &str) -> Array2D<Option<usize>> {
+    try_loop_distances(input).unwrap()
+}
+
+fn try_loop_distances(input: &str) -> Result<Array2D<Option<usize>>, Day10Error> {
+    let (mut grid, start) = parse_grid(input)?;
+
+    let (loop_len, start_tile) = find_loop_length(&start, &grid)?;
+    grid[start] = start_tile;
+
+    let mut distances = Array2D::filled_with(None, grid.num_rows(), grid.num_columns());
+
+    let mut direction = grid[start].unwrap_pipe().0;
+    let mut position = start;
+
+    for steps in 0..loop_len {
+        distances[position] = Some(steps.min(loop_len - steps));
+        position = direction + position;
+        if position != start {
+            direction = grid[position].get_output_dir(direction).unwrap();
+        }
+    }
 
-    let start_position = get_start(&grid);
+    Ok(distances)
+}
 
-    find_loop_length(&start_position, &grid).0 / 2
+/// Find the distance to the farthest point of the loop, giving an error if the
+/// grid is invalid or there is no loop through the start
+pub fn try_part_1(input: &str) -> Result<usize, String> {
+    Ok(try_loop_distances(input)
+        .map_err(|e| e.to_string())?
+        .elements_row_major_iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap())
 }
 
-/// Create mask where it's true if it's a pipe that is part of the main loop
-fn create_pipe_mask(grid: &Array2D<Tile>, start: (usize, usize)) -> Array2D<bool> {
-    let mut mask = Array2D::filled_with(false, grid.num_rows(), grid.num_columns());
+#[aoc(day10, part1)]
+pub fn part_1(input: &str) -> usize {
+    try_part_1(input).unwrap()
+}
 
-    mask[start] = true;
+/// Returns the positions of every pipe in the main loop, in the order they are
+/// visited when walking around the loop from the start
+fn trace_loop(grid: &Array2D<Tile>, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut positions = vec![start];
     let mut direction = grid[start].unwrap_pipe().0;
     let mut position = direction + start;
 
     while position != start {
-        mask[position] = true;
+        positions.push(position);
         direction = grid[position].get_output_dir(direction).unwrap();
         position = direction + position;
     }
 
+    positions
+}
+
+/// Create mask where it's true if it's a pipe that is part of the main loop
+fn create_pipe_mask(grid: &Array2D<Tile>, start: (usize, usize)) -> Array2D<bool> {
+    let mut mask = Array2D::filled_with(false, grid.num_rows(), grid.num_columns());
+
+    for position in trace_loop(grid, start) {
+        mask[position] = true;
+    }
+
     mask
 }
 
-#[aoc(day10, part2)]
-pub fn part_2(input: &str) -> usize {
-    let mut grid = Array2D::from_rows(&input.lines().map(parse_row).collect_vec()).unwrap();
+/// The grid with the start replaced by its actual pipe shape, along with the
+/// main loop through it
+struct MainLoop {
+    grid: Array2D<Tile>,
+    start: (usize, usize),
+    length: usize,
+    /// True for the pipes that are part of the main loop
+    pipe_mask: Array2D<bool>,
+}
 
-    let start_position = get_start(&grid);
+/// Parse the grid and find the main loop through the start
+///
+/// Panics if the grid is invalid or the start isn't part of a loop
+fn find_main_loop(input: &str) -> MainLoop {
+    let (mut grid, start) = parse_grid(input).unwrap();
 
-    grid[start_position] = find_loop_length(&start_position, &grid).1;
+    let (length, start_tile) = find_loop_length(&start, &grid).unwrap();
+    grid[start] = start_tile;
 
-    let pipe_mask = create_pipe_mask(&grid, start_position);
+    let pipe_mask = create_pipe_mask(&grid, start);
 
+    MainLoop {
+        grid,
+        start,
+        length,
+        pipe_mask,
+    }
+}
+
+/// Render the grid with everything other than the main loop erased, and with
+/// the start replaced by its actual pipe shape
+pub fn loop_only_grid(input: &str) -> String {
+    let MainLoop {
+        grid, pipe_mask, ..
+    } = find_main_loop(input);
+
+    let rows: Vec<String> = pipe_mask
+        .rows_iter()
+        .enumerate()
+        .map(|(r, row)| {
+            row.enumerate()
+                .map(|(c, in_loop)| {
+                    if *in_loop {
+                        grid[(r, c)].to_char()
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    rows.join("\n")
+}
+
+#[aoc(day10, part2)]
+pub fn part_2(input: &str) -> usize {
+    let MainLoop {
+        grid, pipe_mask, ..
+    } = find_main_loop(input);
+
+    count_enclosed(&grid, &pipe_mask)
+}
+
+/// Count the tiles enclosed by the main loop, given by the pipe mask, by
+/// scanning each row and tracking whether we're inside the loop
+fn count_enclosed(grid: &Array2D<Tile>, pipe_mask: &Array2D<bool>) -> usize {
     let mut num_contained = 0;
     let mut in_loop = false;
 
@@ -206,10 +409,45 @@ pub fn part_2(input: &str) -> usize {
     num_contained
 }
 
+/// Solve both parts, only parsing the grid and finding the loop once
+pub fn solve_both(input: &str) -> (usize, usize) {
+    let MainLoop {
+        grid,
+        length,
+        pipe_mask,
+        ..
+    } = find_main_loop(input);
+
+    // The farthest point is halfway around the loop
+    (length / 2, count_enclosed(&grid, &pipe_mask))
+}
+
+/// Count the tiles enclosed by the loop using the shoelace formula to find the
+/// loop's area, then Pick's theorem to remove the tiles on the boundary
+pub fn interior_by_pick(input: &str) -> usize {
+    let MainLoop { grid, start, .. } = find_main_loop(input);
+
+    let vertices = trace_loop(&grid, start);
+
+    let double_area = vertices
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| (a.0 * b.1) as i64 - (b.0 * a.1) as i64)
+        .sum::<i64>()
+        .unsigned_abs() as usize;
+
+    // Pick's theorem: area = interior + boundary / 2 - 1
+    double_area / 2 + 1 - vertices.len() / 2
+}
+
 #[cfg(test)]
 mod test {
-    use super::part_1;
-    // use super::part_2;
+    use itertools::Itertools;
+
+    use super::{
+        candidate_start_tiles, interior_by_pick, loop_distances, loop_only_grid, parse_grid,
+        part_1, part_2, solve_both, try_part_1, Day10Error,
+    };
 
     #[test]
     fn test_part_1() {
@@ -225,17 +463,191 @@ mod test {
         )
     }
 
-    // #[test]
-    // fn test_part_2() {
-    //     assert_eq!(
-    //         part_2(
-    //             ".....\n\
-    //             .S-7.\n\
-    //             .|.|.\n\
-    //             .L-J.\n\
-    //             ....."
-    //         ),
-    //         1
-    //     )
-    // }
+    #[test]
+    fn test_loop_distances() {
+        let distances = loop_distances(
+            ".....\n\
+            .S-7.\n\
+            .|.|.\n\
+            .L-J.\n\
+            .....",
+        );
+        assert_eq!(distances[(1, 1)], Some(0));
+        assert_eq!(distances[(1, 3)], Some(2));
+        // Opposite the start
+        assert_eq!(distances[(3, 3)], Some(4));
+        assert_eq!(distances[(2, 2)], None);
+        assert_eq!(distances[(0, 0)], None);
+    }
+
+    #[test]
+    fn test_candidate_start_tiles() {
+        let candidates = candidate_start_tiles(
+            ".....\n\
+            .S-7.\n\
+            .|.|.\n\
+            .L-J.\n\
+            .....",
+        );
+        assert_eq!(candidates.iter().map(|t| t.to_char()).collect_vec(), ['F']);
+
+        // The start joins two separate loops, and could close either of them
+        let candidates = candidate_start_tiles(
+            ".F7.\n\
+            FSJ.\n\
+            LJ..",
+        );
+        assert_eq!(
+            candidates.iter().map(|t| t.to_char()).collect_vec(),
+            ['L', '7']
+        );
+    }
+
+    #[test]
+    fn test_part_1_ragged() {
+        assert_eq!(part_1("..... \n.S-7.\n.|.|   \n.L-J\n..\n"), 4)
+    }
+
+    #[test]
+    fn test_no_loop() {
+        assert_eq!(
+            try_part_1(
+                "...\n\
+                .S.\n\
+                ..."
+            ),
+            Err("The start isn't part of a loop".to_owned())
+        );
+        // Pipes that lead away from the start without returning
+        assert_eq!(
+            try_part_1(
+                ".|.\n\
+                .S-\n\
+                ..."
+            ),
+            Err("The start isn't part of a loop".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_parse_grid_multiple_starts() {
+        assert_eq!(
+            parse_grid(
+                "S-7\n\
+                |.|\n\
+                L-S"
+            )
+            .unwrap_err(),
+            Day10Error::MultipleStarts(vec![(0, 0), (2, 2)])
+        )
+    }
+
+    #[test]
+    fn test_parse_grid_invalid_tile() {
+        assert_eq!(
+            parse_grid(
+                "S-7\n\
+                |X|\n\
+                L-J"
+            )
+            .unwrap_err(),
+            Day10Error::InvalidTile('X', (1, 1))
+        )
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(
+            part_2(
+                ".....\n\
+                .S-7.\n\
+                .|.|.\n\
+                .L-J.\n\
+                ....."
+            ),
+            1
+        )
+    }
+
+    #[test]
+    fn test_solve_both() {
+        let input = ".F----7F7F7F7F-7....\n\
+            .|F--7||||||||FJ....\n\
+            .||.FJ||||||||L7....\n\
+            FJL7L7LJLJ||LJ.L-7..\n\
+            L--J.L7...LJS7F-7L7.\n\
+            ....F-J..F7FJ|L7L7L7\n\
+            ....L7.F7||L7|.L7L7|\n\
+            .....|FJLJ|FJ|F7|.LJ\n\
+            ....FJL-7.||.||||...\n\
+            ....L---J.LJ.LJLJ...";
+        assert_eq!(solve_both(input), (70, 8));
+        assert_eq!(solve_both(input), (part_1(input), part_2(input)));
+    }
+
+    #[test]
+    fn test_loop_only_grid() {
+        assert_eq!(
+            loop_only_grid(
+                "-L|F7\n\
+                7S-7|\n\
+                L|7||\n\
+                -L-J|\n\
+                L|-JF"
+            ),
+            ".....\n\
+            .F-7.\n\
+            .|.|.\n\
+            .L-J.\n\
+            ....."
+        )
+    }
+
+    #[test]
+    fn test_interior_by_pick() {
+        let examples = [
+            "...........\n\
+            .S-------7.\n\
+            .|F-----7|.\n\
+            .||.....||.\n\
+            .||.....||.\n\
+            .|L-7.F-J|.\n\
+            .|..|.|..|.\n\
+            .L--J.L--J.\n\
+            ...........",
+            "..........\n\
+            .S------7.\n\
+            .|F----7|.\n\
+            .||....||.\n\
+            .||....||.\n\
+            .|L-7F-J|.\n\
+            .|..||..|.\n\
+            .L--JL--J.\n\
+            ..........",
+            ".F----7F7F7F7F-7....\n\
+            .|F--7||||||||FJ....\n\
+            .||.FJ||||||||L7....\n\
+            FJL7L7LJLJ||LJ.L-7..\n\
+            L--J.L7...LJS7F-7L7.\n\
+            ....F-J..F7FJ|L7L7L7\n\
+            ....L7.F7||L7|.L7L7|\n\
+            .....|FJLJ|FJ|F7|.LJ\n\
+            ....FJL-7.||.||||...\n\
+            ....L---J.LJ.LJLJ...",
+            "FF7FSF7F7F7F7F7F---7\n\
+            L|LJ||||||||||||F--J\n\
+            FL-7LJLJ||||||LJL-77\n\
+            F--JF--7||LJLJ7F7FJ-\n\
+            L---JF-JLJ.||-FJLJJ7\n\
+            |F|F-JF---7F7-L7L|7|\n\
+            |FFJF7L7F-JF7|JL---7\n\
+            7-L-JL7||F7|L7F-7F7|\n\
+            L.L7LFJ|||||FJL7||LJ\n\
+            L7JLJL-JLJLJL--JLJ.L",
+        ];
+        for (example, expected) in examples.into_iter().zip([4, 4, 8, 10]) {
+            assert_eq!(part_2(example), expected);
+            assert_eq!(interior_by_pick(example), expected);
+        }
+    }
 }
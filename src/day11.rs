@@ -1,3 +1,5 @@
+use std::{cmp::Reverse, collections::HashMap, iter};
+
 use array2d::Array2D;
 use itertools::Itertools;
 
@@ -67,19 +69,71 @@ fn find_pois(image: &Array2D<Pixel>) -> Vec<(usize, usize)> {
         .collect_vec()
 }
 
+/// Whether there are any empty rows or columns to expand, since if there
+/// aren't, the galaxies stay where they are
+fn needs_expansion(empty_rows: &[usize], empty_cols: &[usize]) -> bool {
+    !empty_rows.is_empty() || !empty_cols.is_empty()
+}
+
+/// Returns how far each of the `size` rows (or columns) moves when each of the
+/// empty ones before it is expanded by the given factor
+fn cumulative_offsets(empty: &[usize], size: usize, expansion_factor: usize) -> Vec<usize> {
+    (0..size)
+        .map(|i| empty.iter().filter(|e| **e < i).count() * (expansion_factor - 1))
+        .collect_vec()
+}
+
+/// Make sure that an expansion factor given by the caller won't shrink the
+/// universe
+fn check_expansion(expansion_factor: usize) -> Result<(), String> {
+    if expansion_factor == 0 {
+        Err("Expansion factor must be at least 1".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+/// Find the positions of the galaxies in the image after expanding each empty
+/// row and column by the given factor
 fn expand_pois(
-    pois: &[(usize, usize)],
+    image: &Array2D<Pixel>,
     empty_rows: &[usize],
     empty_cols: &[usize],
     expansion_factor: usize,
 ) -> Vec<(usize, usize)> {
+    let pois = find_pois(image);
+    if !needs_expansion(empty_rows, empty_cols) {
+        return pois;
+    }
+    let row_offsets = cumulative_offsets(empty_rows, image.num_rows(), expansion_factor);
+    let col_offsets = cumulative_offsets(empty_cols, image.num_columns(), expansion_factor);
+    pois.into_iter()
+        .map(|(r, c)| (r + row_offsets[r], c + col_offsets[c]))
+        .collect_vec()
+}
+
+/// Like `expand_pois`, but each empty row and column is expanded to the size
+/// given by its weight. Empty rows/cols without a weight aren't expanded.
+fn expand_pois_weighted(
+    pois: &[(usize, usize)],
+    empty_rows: &[usize],
+    empty_cols: &[usize],
+    row_weights: &HashMap<usize, usize>,
+    col_weights: &HashMap<usize, usize>,
+) -> Vec<(usize, usize)> {
+    let expansion = |empty: &[usize], weights: &HashMap<usize, usize>, pos: usize| -> usize {
+        empty
+            .iter()
+            .filter(|e| **e < pos)
+            .map(|e| weights.get(e).copied().unwrap_or(1) - 1)
+            .sum()
+    };
     pois.iter()
-        .map(|(r, c)| {
-            let new_r =
-                r + empty_rows.iter().filter(|empty_row| *empty_row < r).count() * (expansion_factor - 1);
-            let new_c =
-                c + empty_cols.iter().filter(|empty_col| *empty_col < c).count() * (expansion_factor - 1);
-            (new_r, new_c)
+        .map(|&(r, c)| {
+            (
+                r + expansion(empty_rows, row_weights, r),
+                c + expansion(empty_cols, col_weights, c),
+            )
         })
         .collect_vec()
 }
@@ -105,7 +159,7 @@ pub fn part_1(input: &str) -> usize {
 
     let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
 
-    let galaxy_positions = expand_pois(&find_pois(&image), &empty_rows, &empty_cols, 2);
+    let galaxy_positions = expand_pois(&image, &empty_rows, &empty_cols, 2);
 
     calc_total_distance(&galaxy_positions)
 }
@@ -116,14 +170,168 @@ pub fn part_2(input: &str) -> usize {
 
     let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
 
-    let galaxy_positions = expand_pois(&find_pois(&image), &empty_rows, &empty_cols, 1_000_000);
+    let galaxy_positions = expand_pois(&image, &empty_rows, &empty_cols, 1_000_000);
 
     calc_total_distance(&galaxy_positions)
 }
 
+/// Total distance between all galaxy pairs, where each empty row and column
+/// expands by its own amount. Missing weights default to 1 (no expansion).
+///
+/// Panics if any of the weights are 0, since rows and columns can't shrink.
+pub fn total_distance_weighted(
+    input: &str,
+    row_weights: &HashMap<usize, usize>,
+    col_weights: &HashMap<usize, usize>,
+) -> usize {
+    assert!(
+        row_weights
+            .values()
+            .chain(col_weights.values())
+            .all(|&w| w > 0),
+        "Expansion weights must be at least 1"
+    );
+    let image = parse_image(input);
+
+    let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+
+    let galaxy_positions = expand_pois_weighted(
+        &find_pois(&image),
+        &empty_rows,
+        &empty_cols,
+        row_weights,
+        col_weights,
+    );
+
+    calc_total_distance(&galaxy_positions)
+}
+
+/// Total distance between all galaxy pairs in a universe that wraps around,
+/// where `width` and `height` are the dimensions of the universe after
+/// expansion
+///
+/// Gives an error if the expansion factor is 0, or if the universe is smaller
+/// than the expanded image.
+pub fn total_distance_torus(
+    input: &str,
+    expansion: usize,
+    width: usize,
+    height: usize,
+) -> Result<usize, String> {
+    check_expansion(expansion)?;
+
+    let image = parse_image(input);
+
+    let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+
+    let expanded_height = image.num_rows() + empty_rows.len() * (expansion - 1);
+    let expanded_width = image.num_columns() + empty_cols.len() * (expansion - 1);
+    if height < expanded_height || width < expanded_width {
+        return Err(format!(
+            "A {width}x{height} universe can't fit the {expanded_width}x{expanded_height} image"
+        ));
+    }
+
+    let galaxy_positions = expand_pois(&image, &empty_rows, &empty_cols, expansion);
+
+    Ok(galaxy_positions
+        .iter()
+        .tuple_combinations()
+        .map(|(g1, g2)| {
+            // Going the other way around might be shorter
+            let dr = g1.0.abs_diff(g2.0);
+            let dc = g1.1.abs_diff(g2.1);
+            dr.min(height - dr) + dc.min(width - dc)
+        })
+        .sum())
+}
+
+/// Sum of the `k` largest distances between galaxy pairs, after expanding
+/// each empty row and column by the given factor
+///
+/// Gives an error if the expansion factor is 0.
+pub fn top_k_distance_sum(input: &str, expansion: usize, k: usize) -> Result<usize, String> {
+    check_expansion(expansion)?;
+
+    let image = parse_image(input);
+
+    let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+
+    let galaxy_positions = expand_pois(&image, &empty_rows, &empty_cols, expansion);
+
+    Ok(galaxy_positions
+        .iter()
+        .tuple_combinations()
+        .map(|(g1, g2)| calculate_distance(g1, g2))
+        .sorted_unstable_by_key(|&distance| Reverse(distance))
+        .take(k)
+        .sum())
+}
+
+/// Returns how far each row and each column of the image moves after expanding
+/// each empty row and column by the given factor, as `(rows, columns)`
+///
+/// Gives an error if the expansion factor is 0.
+pub fn axis_offsets(input: &str, expansion: usize) -> Result<(Vec<usize>, Vec<usize>), String> {
+    check_expansion(expansion)?;
+
+    let image = parse_image(input);
+
+    let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+
+    Ok((
+        cumulative_offsets(&empty_rows, image.num_rows(), expansion),
+        cumulative_offsets(&empty_cols, image.num_columns(), expansion),
+    ))
+}
+
+/// Render the universe after expanding each empty row and column by the given
+/// factor. Only practical for small expansion factors.
+pub fn render_expanded(input: &str, expansion: usize) -> String {
+    let image = parse_image(input);
+
+    let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+
+    let rows = image
+        .rows_iter()
+        .enumerate()
+        .flat_map(|(r, row)| {
+            let line: String = row
+                .enumerate()
+                .flat_map(|(c, pixel)| {
+                    let pixel = if pixel.is_galaxy() { '#' } else { '.' };
+                    let width = if empty_cols.contains(&c) { expansion } else { 1 };
+                    iter::repeat_n(pixel, width)
+                })
+                .collect();
+            let height = if empty_rows.contains(&r) { expansion } else { 1 };
+            iter::repeat_n(line, height)
+        })
+        .collect_vec();
+
+    rows.join("\n")
+}
+
 #[cfg(test)]
 mod test {
-    use super::part_1;
+    use std::collections::HashMap;
+
+    use super::{
+        axis_offsets, calc_total_distance, find_empty_rows_cols, find_pois, needs_expansion,
+        parse_image, part_1, render_expanded, top_k_distance_sum, total_distance_torus,
+        total_distance_weighted,
+    };
+
+    const SAMPLE: &str = "...#......\n\
+        .......#..\n\
+        #.........\n\
+        ..........\n\
+        ......#...\n\
+        .#........\n\
+        .........#\n\
+        ..........\n\
+        .......#..\n\
+        #...#.....";
 
     #[test]
     fn test_calc_distances() {
@@ -147,22 +355,120 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_dense_image() {
+        let input = "#.#\n\
+            .#.";
+        let image = parse_image(input);
+        let (empty_rows, empty_cols) = find_empty_rows_cols(&image);
+        assert!(!needs_expansion(&empty_rows, &empty_cols));
+        assert_eq!(part_1(input), calc_total_distance(&find_pois(&image)));
+        assert_eq!(part_1(input), 6);
+    }
+
     #[test]
     fn test_part_1() {
+        assert_eq!(part_1(SAMPLE), 374)
+    }
+
+    #[test]
+    fn test_axis_offsets() {
+        let (rows, cols) = axis_offsets(SAMPLE, 2).unwrap();
+        // Rows 3 and 7 are empty
+        assert_eq!(rows, [0, 0, 0, 0, 1, 1, 1, 1, 2, 2]);
+        // Columns 2, 5 and 8 are empty
+        assert_eq!(cols, [0, 0, 0, 1, 1, 1, 2, 2, 2, 3]);
+
+        let (rows, _) = axis_offsets(SAMPLE, 10).unwrap();
+        assert_eq!(rows[9], 18);
+
         assert_eq!(
-            part_1(
-                "...#......\n\
-                 .......#..\n\
-                 #.........\n\
-                 ..........\n\
-                 ......#...\n\
-                 .#........\n\
-                 .........#\n\
-                 ..........\n\
-                 .......#..\n\
-                 #...#....."
+            axis_offsets(SAMPLE, 0),
+            Err("Expansion factor must be at least 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_top_k_distance_sum() {
+        // The longest distances are 19, 17 and 17
+        assert_eq!(top_k_distance_sum(SAMPLE, 2, 3), Ok(53));
+        assert_eq!(top_k_distance_sum(SAMPLE, 2, 0), Ok(0));
+        // Taking every pair gives the full total
+        assert_eq!(top_k_distance_sum(SAMPLE, 2, 1000), Ok(374));
+
+        assert_eq!(
+            top_k_distance_sum(SAMPLE, 0, 3),
+            Err("Expansion factor must be at least 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_weighted_expansion() {
+        assert_eq!(
+            total_distance_weighted(
+                "#\n\
+                 .\n\
+                 #\n\
+                 .\n\
+                 #",
+                &HashMap::from([(1, 10), (3, 100)]),
+                &HashMap::new(),
+            ),
+            // Galaxies end up at rows 0, 11 and 112
+            11 + 112 + 101
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn test_zero_weight() {
+        total_distance_weighted("#\n.\n#", &HashMap::from([(1, 0)]), &HashMap::new());
+    }
+
+    #[test]
+    fn test_torus_distance() {
+        // Galaxies expand to columns 0 and 7 of 8, so are 1 apart when
+        // wrapping around
+        assert_eq!(total_distance_torus("#...#", 2, 8, 1), Ok(1));
+
+        // The expanded sample is 12 rows by 13 columns
+        assert!(total_distance_torus(SAMPLE, 2, 13, 12).unwrap() < part_1(SAMPLE));
+        // But a big enough universe never benefits from wrapping
+        assert_eq!(
+            total_distance_torus(SAMPLE, 2, 1000, 1000),
+            Ok(part_1(SAMPLE))
+        );
+    }
+
+    #[test]
+    fn test_torus_smaller_than_image() {
+        assert_eq!(
+            total_distance_torus("#...#", 2, 7, 1),
+            Err("A 7x1 universe can't fit the 8x1 image".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_torus_zero_expansion() {
+        assert_eq!(
+            total_distance_torus("#...#", 0, 8, 1),
+            Err("Expansion factor must be at least 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_render_expanded() {
+        assert_eq!(
+            render_expanded(
+                "#..\n\
+                 ...\n\
+                 ..#",
+                2
             ),
-            374
+            "#...\n\
+             ....\n\
+             ....\n\
+             ...#"
         )
     }
 }
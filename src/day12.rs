@@ -1,30 +1,10 @@
-use std::{collections::HashMap, fs, iter, ops::{Deref, DerefMut}, sync::RwLock};
+use std::{collections::HashMap, iter, sync::Mutex};
 
 use itertools::Itertools;
 use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
-
-const RESULTS_FILE: &str = "../../../temp/day12.json";
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct SavedResults(HashMap<String, usize>);
-
-impl Deref for SavedResults {
-    type Target = HashMap<String, usize>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl DerefMut for SavedResults {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum SpringState {
+pub enum SpringState {
     Safe,
     Damaged,
     Unknown,
@@ -49,6 +29,20 @@ fn load_spring_states(info: &str) -> Vec<SpringState> {
     info.chars().map_into().collect_vec()
 }
 
+/// Trim the runs of safe springs from both ends of the record, since they
+/// can't affect the number of arrangements
+fn trim_safe(springs: &mut [SpringState]) -> &mut [SpringState] {
+    let start = springs
+        .iter()
+        .position(|s| *s != SpringState::Safe)
+        .unwrap_or(springs.len());
+    let end = springs
+        .iter()
+        .rposition(|s| *s != SpringState::Safe)
+        .map_or(start, |end| end + 1);
+    &mut springs[start..end]
+}
+
 /// Returns the remaining slices of the springs and damage info
 /// This allows us to skip over checking the starting parts, which we know are
 /// correct (since we already checked them earlier in the recursion)
@@ -113,58 +107,132 @@ fn get_remaining_springs<'a>(
     }
 }
 
-/// Returns the index of the next unknown spring state in the slice
-fn find_unknown(springs: &[SpringState]) -> usize {
+/// Count the number of combinations of spring states from `spring_idx` onwards
+/// that match the damage info from `damage_idx` onwards, memoising the count
+/// for each pair of indices
+fn count_matching_combos(
+    springs: &[SpringState],
+    damage_info: &[usize],
+    spring_idx: usize,
+    damage_idx: usize,
+    memo: &mut HashMap<(usize, usize), usize>,
+) -> usize {
+    let Some(&group_len) = damage_info.get(damage_idx) else {
+        // All the groups are placed, so the rest must be able to be safe
+        return usize::from(!springs[spring_idx..].contains(&SpringState::Damaged));
+    };
+    if spring_idx >= springs.len() {
+        return 0;
+    }
+    if let Some(count) = memo.get(&(spring_idx, damage_idx)) {
+        return *count;
+    }
+
+    let mut count = 0;
+    // Treat this spring as safe
+    if springs[spring_idx] != SpringState::Damaged {
+        count += count_matching_combos(springs, damage_info, spring_idx + 1, damage_idx, memo);
+    }
+    // Start the next group of damaged springs here, which needs a safe spring
+    // (or the end of the record) straight after it
+    let group_end = spring_idx + group_len;
+    if group_end <= springs.len()
+        && !springs[spring_idx..group_end].contains(&SpringState::Safe)
+        && springs.get(group_end) != Some(&SpringState::Damaged)
+    {
+        let next_idx = (group_end + 1).min(springs.len());
+        count += count_matching_combos(springs, damage_info, next_idx, damage_idx + 1, memo);
+    }
+
+    memo.insert((spring_idx, damage_idx), count);
+    count
+}
+
+/// Count the arrangements of the springs that match the given damage info,
+/// giving up straight away if there aren't enough springs that could be
+/// damaged to fit the groups
+fn count_arrangements(springs: &[SpringState], damage_info: &[usize]) -> usize {
+    let num_damageable = springs.iter().filter(|s| **s != SpringState::Safe).count();
+    if damage_info.iter().sum::<usize>() > num_damageable {
+        return 0;
+    }
+    count_matching_combos(springs, damage_info, 0, 0, &mut HashMap::new())
+}
+
+/// Returns whether a fully-resolved record matches the given damaged groups
+///
+/// Panics if any of the springs are unknown.
+pub fn matches_groups(springs: &[SpringState], groups: &[usize]) -> bool {
+    assert!(
+        !springs.contains(&SpringState::Unknown),
+        "Can't check a record with unknown springs"
+    );
     springs
-        .iter()
-        .find_position(|s| *s == &SpringState::Unknown)
-        .unwrap()
-        .0
+        .split(|s| *s == SpringState::Safe)
+        .filter(|group| !group.is_empty())
+        .map(<[SpringState]>::len)
+        .eq(groups.iter().copied())
 }
 
-/// Count the number of combinations of spring states that match the given
-/// damage info slice
-fn count_matching_combos(springs: &mut [SpringState], damage_info: &[usize]) -> usize {
-    if let Some((springs, damage_info)) = get_remaining_springs(springs, damage_info) {
-        if springs.is_empty() {
-            1
-        } else {
-            let unknown_position = find_unknown(springs);
+/// Push every combination of spring states that matches the given damage info
+/// onto `arrangements`
+fn collect_matching_combos(
+    springs: &mut [SpringState],
+    damage_info: &[usize],
+    arrangements: &mut Vec<Vec<SpringState>>,
+) {
+    // Give up early if what we've decided so far can't match
+    if get_remaining_springs(springs, damage_info).is_none() {
+        return;
+    }
 
-            // Try all combinations
-            let mut count = 0;
-            for state in [SpringState::Damaged, SpringState::Safe] {
-                springs[unknown_position] = state;
-                count += count_matching_combos(springs, damage_info);
-            }
-            // And reset it back to unknown for the next iteration
-            springs[unknown_position] = SpringState::Unknown;
-            count
+    if let Some(unknown_position) = springs.iter().position(|s| *s == SpringState::Unknown) {
+        for state in [SpringState::Damaged, SpringState::Safe] {
+            springs[unknown_position] = state;
+            collect_matching_combos(springs, damage_info, arrangements);
         }
-    } else {
-        0
+        springs[unknown_position] = SpringState::Unknown;
+    } else if matches_groups(springs, damage_info) {
+        arrangements.push(springs.to_vec());
     }
 }
 
+/// Returns every fully-resolved arrangement of the springs that matches the
+/// given damaged groups
+///
+/// This grows exponentially with the number of unknown springs, so is only
+/// feasible for short records
+pub fn enumerate_arrangements(springs: &[SpringState], groups: &[usize]) -> Vec<Vec<SpringState>> {
+    let mut arrangements = vec![];
+    collect_matching_combos(&mut springs.to_vec(), groups, &mut arrangements);
+    arrangements
+}
+
+/// Count the arrangements for a single `springs groups` record, such as
+/// `???.### 1,1,3`
+pub fn count_record(line: &str) -> usize {
+    let (springs, damages) = line.split_once(' ').unwrap();
+    count_arrangements(
+        trim_safe(&mut load_spring_states(springs)),
+        &load_damages_vec(damages),
+    )
+}
+
 #[aoc(day12, part1)]
 pub fn part_1(input: &str) -> usize {
-    input
-        .lines()
-        .map(|line| line.split_once(' ').unwrap())
-        .map(|(springs, damages)| (load_spring_states(springs), load_damages_vec(damages)))
-        .map(|(mut springs, damages)| count_matching_combos(&mut springs, &damages))
-        .sum()
+    input.lines().map(count_record).sum()
 }
 
 #[aoc(day12, part2)]
 pub fn part_2(input: &str) -> usize {
-    // Load the saved results from a file
-    // This is helpful because I can't be bothered to optimise it, and want to
-    // be able to stop and start the processing
-    let saved_results: SavedResults =
-        serde_json::from_str(&fs::read_to_string(RESULTS_FILE).unwrap()).unwrap();
+    part_2_with_progress(input, |_, _| {})
+}
 
-    let saved_results_mut = RwLock::new(saved_results.clone());
+/// Solve part 2, calling `on_record` with the index of each record and its
+/// number of arrangements as each one is solved
+pub fn part_2_with_progress(input: &str, on_record: impl FnMut(usize, usize) + Send) -> usize {
+    // Records are solved in parallel, so the callback needs to be shared
+    let on_record = Mutex::new(on_record);
 
     input
         .lines()
@@ -187,46 +255,26 @@ pub fn part_2(input: &str) -> usize {
                 damages.repeat(5),
             )
         })
-        // Do the calculation in parallel, because otherwise it is even slower
-        // This still takes about 15 minutes even with this optimisation, but
-        // I really can't be bothered to make it any faster
+        // Do the calculation in parallel
         .enumerate()
         .par_bridge()
         .map(|(i, (mut springs, damages))| {
-            (
-                i,
-                // If it exists in the saved results
-                saved_results
-                    .get(&i.to_string())
-                    .copied()
-                    // Otherwise, calculate it
-                    .unwrap_or_else(|| {
-                        println!("Calculating {}...", i);
-                        count_matching_combos(&mut springs, &damages)
-                    }),
-            )
-        })
-        .inspect(|(i, e)| {
-            // Only if it hasn't been calculated before
-            if saved_results.get(&i.to_string()).is_none() {
-                // Print the result
-                println!("[{i}]\t{e}");
-
-                // Add it to the data
-                let mut results = saved_results_mut.write().unwrap();
-                results.insert(i.to_string(), *e);
-
-                // Then save the file
-                fs::write(RESULTS_FILE, serde_json::to_string(&results.0).unwrap()).unwrap();
-            }
+            (i, count_arrangements(trim_safe(&mut springs), &damages))
         })
+        .inspect(|(i, e)| on_record.lock().unwrap()(*i, *e))
         .map(|(_, e)| e)
         .sum()
 }
 
 #[cfg(test)]
 mod test {
-    use super::{part_1, part_2};
+    use std::time::{Duration, Instant};
+
+    use super::{
+        count_record, enumerate_arrangements, load_spring_states, matches_groups, part_1, part_2,
+        part_2_with_progress, trim_safe,
+        SpringState::{Damaged, Safe},
+    };
 
     #[test]
     fn test_part_1() {
@@ -253,8 +301,81 @@ mod test {
         assert_eq!(part_1("????.######..#####. 1,6,5"), 4,)
     }
 
+    #[test]
+    fn test_count_record() {
+        assert_eq!(count_record("?###???????? 3,2,1"), 10);
+        assert_eq!(count_record("???.### 1,1,3"), 1);
+    }
+
+    #[test]
+    fn test_trim_safe() {
+        let mut springs = load_spring_states("..?#.?...");
+        assert_eq!(trim_safe(&mut springs), load_spring_states("?#.?"));
+        assert!(trim_safe(&mut load_spring_states("...")).is_empty());
+
+        assert_eq!(
+            count_record("...??.### 1,1,3"),
+            count_record("??.### 1,1,3")
+        );
+        assert_eq!(count_record("..?#?.. 2"), count_record("?#? 2"));
+    }
+
     #[test]
     fn test_part_2() {
         assert_eq!(part_2(""), 0)
     }
+
+    #[test]
+    fn test_part_2_with_progress() {
+        let mut solved = vec![];
+        let total = part_2_with_progress(
+            "???.### 1,1,3\n\
+            .??..??...?##. 1,1,3\n\
+            ?#?#?#?#?#?#?#? 1,3,1,6",
+            |i, count| solved.push((i, count)),
+        );
+        solved.sort();
+        assert_eq!(solved, [(0, 1), (1, 16384), (2, 1)]);
+        assert_eq!(total, 16386);
+    }
+
+    #[test]
+    fn test_enumerate_arrangements() {
+        assert_eq!(
+            enumerate_arrangements(&load_spring_states("???.###"), &[1, 1, 3]),
+            [[Damaged, Safe, Damaged, Safe, Damaged, Damaged, Damaged]]
+        );
+        assert_eq!(
+            enumerate_arrangements(&load_spring_states(".??..??...?##."), &[1, 1, 3]).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_matches_groups() {
+        let springs = load_spring_states("#.###");
+        assert!(matches_groups(&springs, &[1, 3]));
+        assert!(!matches_groups(&springs, &[1, 2]));
+        assert!(!matches_groups(&springs, &[1]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_matches_groups_unknown() {
+        matches_groups(&load_spring_states("#.?##"), &[1, 3]);
+    }
+
+    #[test]
+    fn test_not_enough_damageable_springs() {
+        assert_eq!(count_record(".. 5"), 0);
+        assert_eq!(count_record("#?.# 2,2"), 0);
+        assert_eq!(count_record("#?.# 2,1"), 1);
+    }
+
+    #[test]
+    fn test_part_2_hardest_sample_is_fast() {
+        let start = Instant::now();
+        assert_eq!(part_2("?###???????? 3,2,1"), 506250);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
 }
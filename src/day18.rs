@@ -13,27 +13,40 @@ enum Direction {
     Right,
 }
 
+/// Letter used to represent each direction in the dig plan
+const DIRECTION_LETTERS: [(&str, Direction); 4] =
+    [("U", UP), ("D", DOWN), ("L", LEFT), ("R", RIGHT)];
+
+/// Hex digit used to represent each direction in the colour codes
+const HEX_DIRECTIONS: [(char, Direction); 4] = [('0', RIGHT), ('1', DOWN), ('2', LEFT), ('3', UP)];
+
+/// Find the direction matching the given key in a direction table
+fn lookup_direction<K: PartialEq>(table: &[(K, Direction)], key: K) -> Direction {
+    table
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, direction)| *direction)
+        .expect("Invalid direction")
+}
+
+/// Find the key matching the given direction in a direction table
+fn lookup_key<K: Copy>(table: &[(K, Direction)], direction: Direction) -> K {
+    table
+        .iter()
+        .find(|(_, d)| *d == direction)
+        .map(|(k, _)| *k)
+        .unwrap()
+}
+
 impl From<&str> for Direction {
     fn from(value: &str) -> Self {
-        match value {
-            "U" => UP,
-            "D" => DOWN,
-            "L" => LEFT,
-            "R" => RIGHT,
-            _ => panic!("Invalid direction"),
-        }
+        lookup_direction(&DIRECTION_LETTERS, value)
     }
 }
 
 impl From<char> for Direction {
     fn from(value: char) -> Self {
-        match value {
-            '3' => UP,
-            '1' => DOWN,
-            '2' => LEFT,
-            '0' => RIGHT,
-            _ => panic!("Invalid direction"),
-        }
+        lookup_direction(&HEX_DIRECTIONS, value)
     }
 }
 
@@ -106,28 +119,55 @@ struct VerticalTrenchLocation {
     ttype: TrenchType,
 }
 
+impl VerticalTrenchLocation {
+    /// Create a vertical trench in the given column, covering rows `top` to
+    /// `bottom`
+    fn new(horizontal: i32, top: i32, bottom: i32, ttype: TrenchType) -> Self {
+        VerticalTrenchLocation {
+            horizontal,
+            top,
+            bottom,
+            ttype,
+        }
+    }
+}
+
 // Location of a horizontal trench (excluding the row number, since we store that elsewhere)
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 struct HorizontalTrenchLocation(i32, i32);
 
+impl HorizontalTrenchLocation {
+    /// Create a horizontal trench covering the given columns, which may be
+    /// given in either order
+    fn new(left: i32, right: i32) -> Self {
+        HorizontalTrenchLocation(left.min(right), left.max(right))
+    }
+}
+
 #[derive(Debug)]
 enum TrenchLocation {
     Vertical(VerticalTrenchLocation),
     Horizontal(i32, HorizontalTrenchLocation),
 }
 
+impl TrenchLocation {
+    /// Whether the trench was dug by a zero-length instruction, and so doesn't
+    /// cover any tiles beyond its neighbours
+    fn is_degenerate(&self) -> bool {
+        match self {
+            TrenchLocation::Vertical(v) => v.top == v.bottom,
+            TrenchLocation::Horizontal(_, h) => h.0 == h.1,
+        }
+    }
+}
+
 fn parse_trench_location_1(
     position: &mut Coord,
     border_positions: &mut (i32, i32),
     input: &str,
 ) -> TrenchLocation {
-    // R 6 (#70c710)
-    // ^ ^
-    let (dir, amount, _) = input.split(' ').collect_tuple().unwrap();
-
-    let direction = Direction::from(dir);
-
-    let displacement = direction * amount.parse::<i32>().unwrap();
+    let displacement = parse_plan_displacement(input);
+    let direction = displacement.direction;
 
     parse_trench_location_common(position, displacement, direction, border_positions)
 }
@@ -137,6 +177,23 @@ fn parse_trench_location_2(
     border_positions: &mut (i32, i32),
     input: &str,
 ) -> TrenchLocation {
+    let displacement = parse_hex_displacement(input);
+    let direction = displacement.direction;
+
+    parse_trench_location_common(position, displacement, direction, border_positions)
+}
+
+/// Parse the displacement given by the direction and amount of a line
+fn parse_plan_displacement(input: &str) -> Displacement {
+    // R 6 (#70c710)
+    // ^ ^
+    let (dir, amount, _) = input.split(' ').collect_tuple().unwrap();
+
+    Direction::from(dir) * amount.parse::<i32>().unwrap()
+}
+
+/// Parse the displacement that is encoded within the colour of a line
+fn parse_hex_displacement(input: &str) -> Displacement {
     //       vvvvvv
     // R 6 (#70c710)
     let info_str = input
@@ -157,9 +214,36 @@ fn parse_trench_location_2(
     // R 6 (#70c710)
     let direction = Direction::from(info_str.chars().nth(5).unwrap());
 
-    let displacement = direction * distance;
+    direction * distance
+}
 
-    parse_trench_location_common(position, displacement, direction, border_positions)
+/// Calculate the area dug out by following the given displacements, using the
+/// shoelace formula
+///
+/// All arithmetic is done using `i128` so that it can't overflow, even for
+/// enormous trenches
+fn shoelace_area(displacements: impl IntoIterator<Item = Displacement>) -> i128 {
+    let mut position = (0i128, 0i128);
+    let mut twice_area = 0i128;
+    let mut perimeter = 0i128;
+
+    for displacement in displacements {
+        let amount = displacement.amount as i128;
+        let next = match displacement.direction {
+            UP => (position.0 - amount, position.1),
+            DOWN => (position.0 + amount, position.1),
+            LEFT => (position.0, position.1 - amount),
+            RIGHT => (position.0, position.1 + amount),
+        };
+        twice_area += position.0 * next.1 - next.0 * position.1;
+        perimeter += amount;
+        position = next;
+    }
+
+    // The shoelace formula only gives the area enclosed by the middle of the
+    // trench, so by Pick's theorem we need to add the outer half of the
+    // trench, plus an extra 1 for the corners
+    twice_area.abs() / 2 + perimeter / 2 + 1
 }
 
 fn parse_trench_location_common(
@@ -173,29 +257,22 @@ fn parse_trench_location_common(
     let lower_position = i32::min(result_position.0, position.0);
     let upper_position = i32::max(result_position.0, position.0);
 
-    let leftmost_position = i32::min(result_position.1, position.1);
-    let rightmost_position = i32::max(result_position.1, position.1);
-
     let result = match direction {
-        Direction::Up => TrenchLocation::Vertical(VerticalTrenchLocation {
-            horizontal: position.1,
-            top: lower_position,
-            bottom: upper_position,
-            ttype: TrenchType::Upwards,
-        }),
-        Direction::Down => TrenchLocation::Vertical(VerticalTrenchLocation {
-            horizontal: position.1,
-            top: lower_position,
-            bottom: upper_position,
-            ttype: TrenchType::Downwards,
-        }),
-        Direction::Left => TrenchLocation::Horizontal(
+        Direction::Up => TrenchLocation::Vertical(VerticalTrenchLocation::new(
+            position.1,
             lower_position,
-            HorizontalTrenchLocation(leftmost_position, rightmost_position),
-        ),
-        Direction::Right => TrenchLocation::Horizontal(
+            upper_position,
+            TrenchType::Upwards,
+        )),
+        Direction::Down => TrenchLocation::Vertical(VerticalTrenchLocation::new(
+            position.1,
+            lower_position,
+            upper_position,
+            TrenchType::Downwards,
+        )),
+        Direction::Left | Direction::Right => TrenchLocation::Horizontal(
             lower_position,
-            HorizontalTrenchLocation(leftmost_position, rightmost_position),
+            HorizontalTrenchLocation::new(position.1, result_position.1),
         ),
     };
 
@@ -211,20 +288,27 @@ fn parse_trench_location_common(
     result
 }
 
-fn solve(
-    parse_strategy: fn(
-        position: &mut Coord,
-        border_positions: &mut (i32, i32),
-        input: &str,
-    ) -> TrenchLocation,
-    input: &str,
-) -> usize {
+type ParseStrategy =
+    fn(position: &mut Coord, border_positions: &mut (i32, i32), input: &str) -> TrenchLocation;
+
+fn solve(parse_strategy: ParseStrategy, input: &str) -> usize {
+    fill_counts(parse_strategy, input)
+        .into_iter()
+        .map(|(_, num_filled)| num_filled)
+        .sum()
+}
+
+/// Find the number of filled cells in each row of the lagoon, using the given
+/// parse strategy
+fn fill_counts(parse_strategy: ParseStrategy, input: &str) -> Vec<(i32, usize)> {
     let mut position = START_POS;
     let mut border_positions = (0, 0);
 
     let trenches = input
         .lines()
         .map(|line| parse_strategy(&mut position, &mut border_positions, line))
+        // Zero-length trenches would confuse the scanline, so skip them
+        .filter(|trench| !trench.is_degenerate())
         .collect_vec();
 
     let vertical_trenches = trenches
@@ -253,19 +337,16 @@ fn solve(
         v.sort_unstable();
     }
 
-    let mut num_filled = 0usize;
-
-    for row in border_positions.0..=border_positions.1 {
-        let num_filled_row = calc_area_filled_row(
-            &vertical_trenches,
-            horizontal_trenches.get(&row).unwrap_or(&vec![]),
-            row,
-        );
-
-        num_filled += num_filled_row;
-    }
-
-    num_filled
+    (border_positions.0..=border_positions.1)
+        .map(|row| {
+            let num_filled_row = calc_area_filled_row(
+                &vertical_trenches,
+                horizontal_trenches.get(&row).unwrap_or(&vec![]),
+                row,
+            );
+            (row, num_filled_row)
+        })
+        .collect()
 }
 
 fn calc_area_filled_row(
@@ -427,6 +508,67 @@ fn calc_area_filled_row(
 //     }
 // }
 
+/// Generate a dig plan that follows the given vertices in order, before
+/// returning to the first one
+///
+/// Each pair of consecutive vertices must be in the same row or column, and
+/// at most `0xfffff` apart so that the distance fits in the colour. The colour
+/// of each instruction encodes the same displacement, so the plan gives the
+/// same area for both parts.
+pub fn vertices_to_plan(vertices: &[(i32, i32)]) -> String {
+    vertices
+        .iter()
+        .circular_tuple_windows()
+        .filter(|(from, to)| from != to)
+        .map(|(from, to)| {
+            let (direction, amount) = match (to.0 - from.0, to.1 - from.1) {
+                (0, c) if c > 0 => (RIGHT, c),
+                (0, c) => (LEFT, -c),
+                (r, 0) if r > 0 => (DOWN, r),
+                (r, 0) => (UP, -r),
+                _ => panic!("Vertices {from:?} and {to:?} aren't axis-aligned"),
+            };
+            assert!(
+                amount <= 0xfffff,
+                "Vertices {from:?} and {to:?} are too far apart to encode as a colour"
+            );
+            format!(
+                "{} {amount} (#{amount:05x}{})",
+                lookup_key(&DIRECTION_LETTERS, direction),
+                lookup_key(&HEX_DIRECTIONS, direction)
+            )
+        })
+        .join("\n")
+}
+
+/// Find the number of filled cells in each row of the lagoon as `(row, filled)`
+/// pairs, reading the plan from the colour codes if `use_hex` is set
+///
+/// This uses the scanline approach, so is useful for debugging, but is far too
+/// slow for the full part 2 input.
+pub fn row_fill_counts(input: &str, use_hex: bool) -> Vec<(i32, usize)> {
+    if use_hex {
+        fill_counts(parse_trench_location_2, input)
+    } else {
+        fill_counts(parse_trench_location_1, input)
+    }
+}
+
+/// Find the area enclosed by the trench, not including the trench itself,
+/// reading the plan from the colour codes if `use_hex` is set
+pub fn interior_area(input: &str, use_hex: bool) -> usize {
+    let parse_displacement = if use_hex {
+        parse_hex_displacement
+    } else {
+        parse_plan_displacement
+    };
+    let displacements = input.lines().map(parse_displacement).collect_vec();
+
+    let perimeter: i128 = displacements.iter().map(|d| d.amount as i128).sum();
+
+    usize::try_from(shoelace_area(displacements) - perimeter).unwrap()
+}
+
 #[aoc(day18, part1)]
 pub fn part_1(input: &str) -> usize {
     solve(parse_trench_location_1, input)
@@ -434,7 +576,20 @@ pub fn part_1(input: &str) -> usize {
 
 #[aoc(day18, part2)]
 pub fn part_2(input: &str) -> usize {
-    solve(parse_trench_location_2, input)
+    usize::try_from(shoelace_area(input.lines().map(parse_hex_displacement))).unwrap()
+}
+
+/// Solve both parts while only reading through the input once
+pub fn solve_both(input: &str) -> (usize, usize) {
+    let (plan, hex): (Vec<_>, Vec<_>) = input
+        .lines()
+        .map(|line| (parse_plan_displacement(line), parse_hex_displacement(line)))
+        .unzip();
+
+    (
+        usize::try_from(shoelace_area(plan)).unwrap(),
+        usize::try_from(shoelace_area(hex)).unwrap(),
+    )
 }
 
 #[cfg(test)]
@@ -445,6 +600,11 @@ mod test {
     use super::calc_area_filled_row;
     use super::part_1;
     use super::part_2;
+    use super::{
+        interior_area, parse_hex_displacement, row_fill_counts, shoelace_area, solve_both,
+        vertices_to_plan, Direction, DOWN, LEFT, RIGHT, START_POS, UP,
+    };
+    use itertools::Itertools;
 
     #[test]
     fn test_part_1() {
@@ -804,43 +964,30 @@ mod test {
     //             8
     //         )
     //     }
-    //
-    //     #[test]
-    //     fn test_calc_area_wavy_3() {
-    //         assert_eq!(
-    //             calc_area_filled_row(
-    //                 &[
-    //                     VerticalTrenchLocation {
-    //                         horizontal: 0,
-    //                         top: -1,
-    //                         bottom: 0,
-    //                         ttype: TrenchType::Upwards
-    //                     },
-    //                     VerticalTrenchLocation {
-    //                         horizontal: 2,
-    //                         top: -1,
-    //                         bottom: 0,
-    //                         ttype: TrenchType::Downwards
-    //                     },
-    //                     VerticalTrenchLocation {
-    //                         horizontal: 4,
-    //                         top: -1,
-    //                         bottom: 0,
-    //                         ttype: TrenchType::Upwards
-    //                     },
-    //                     VerticalTrenchLocation {
-    //                         horizontal: 6,
-    //                         top: -1,
-    //                         bottom: 0,
-    //                         ttype: TrenchType::Downwards
-    //                     },
-    //                 ],
-    //                 0,
-    //             ),
-    //             6
-    //         )
-    //     }
-    //
+
+    #[test]
+    fn test_calc_area_wavy_3() {
+        // The bottoms of two dips, which leave a gap between them
+        // #######
+        // ###.###
+        assert_eq!(
+            calc_area_filled_row(
+                &[
+                    VerticalTrenchLocation::new(0, -1, 0, TrenchType::Upwards),
+                    VerticalTrenchLocation::new(2, -1, 0, TrenchType::Downwards),
+                    VerticalTrenchLocation::new(4, -1, 0, TrenchType::Upwards),
+                    VerticalTrenchLocation::new(6, -1, 0, TrenchType::Downwards),
+                ],
+                &[
+                    HorizontalTrenchLocation::new(0, 2),
+                    HorizontalTrenchLocation::new(4, 6),
+                ],
+                0,
+            ),
+            6
+        )
+    }
+
     #[test]
     fn test_calc_area_wavy_4() {
         assert_eq!(
@@ -976,4 +1123,143 @@ mod test {
             952408144115
         )
     }
+
+    #[test]
+    fn test_part_2_huge_rectangle() {
+        let input = "R 1 (#fffff0)\n\
+            D 1 (#fffff1)\n\
+            L 1 (#fffff2)\n\
+            U 1 (#fffff3)";
+        // Each side covers 0xfffff + 1 cells, including the start
+        let side = 0xfffff_i128 + 1;
+        assert_eq!(
+            shoelace_area(input.lines().map(parse_hex_displacement)),
+            side * side
+        );
+        assert_eq!(part_2(input) as i128, side * side);
+    }
+
+    #[test]
+    fn test_direction_tables() {
+        assert_eq!(Direction::from('0'), RIGHT);
+        assert_eq!(Direction::from('1'), DOWN);
+        assert_eq!(Direction::from('2'), LEFT);
+        assert_eq!(Direction::from('3'), UP);
+
+        assert_eq!(Direction::from("R"), RIGHT);
+        assert_eq!(Direction::from("D"), DOWN);
+        assert_eq!(Direction::from("L"), LEFT);
+        assert_eq!(Direction::from("U"), UP);
+    }
+
+    #[test]
+    fn test_vertices_to_plan_round_trip() {
+        let input = "R 6 (#70c710)\n\
+            D 5 (#0dc571)\n\
+            L 2 (#5713f0)\n\
+            D 2 (#d2c081)\n\
+            R 2 (#59c680)\n\
+            D 2 (#411b91)\n\
+            L 5 (#8ceee2)\n\
+            U 2 (#caa173)\n\
+            L 1 (#1b58a2)\n\
+            U 2 (#caa171)\n\
+            R 2 (#7807d2)\n\
+            U 3 (#a77fa3)\n\
+            L 2 (#015232)\n\
+            U 2 (#7a21e3)";
+        let vertices = input
+            .lines()
+            .scan(START_POS, |position, line| {
+                let (dir, amount, _) = line.split(' ').collect_tuple().unwrap();
+                *position = *position + Direction::from(dir) * amount.parse::<i32>().unwrap();
+                Some(*position)
+            })
+            .collect_vec();
+
+        let plan = vertices_to_plan(&vertices);
+        assert_eq!(plan.lines().next(), Some("D 5 (#000051)"));
+        assert_eq!(part_1(&plan), 62);
+        assert_eq!(part_2(&plan), 62);
+    }
+
+    #[test]
+    #[should_panic(expected = "too far apart")]
+    fn test_vertices_to_plan_too_far() {
+        vertices_to_plan(&[(0, 0), (0, 0x100000), (1, 0x100000), (1, 0)]);
+    }
+
+    #[test]
+    fn test_solve_both() {
+        assert_eq!(
+            solve_both(
+                "R 6 (#70c710)\n\
+                D 5 (#0dc571)\n\
+                L 2 (#5713f0)\n\
+                D 2 (#d2c081)\n\
+                R 2 (#59c680)\n\
+                D 2 (#411b91)\n\
+                L 5 (#8ceee2)\n\
+                U 2 (#caa173)\n\
+                L 1 (#1b58a2)\n\
+                U 2 (#caa171)\n\
+                R 2 (#7807d2)\n\
+                U 3 (#a77fa3)\n\
+                L 2 (#015232)\n\
+                U 2 (#7a21e3)"
+            ),
+            (62, 952408144115)
+        )
+    }
+
+    #[test]
+    fn test_zero_length_segment() {
+        // #v
+        // |>v
+        // ^-<
+        let plan = "R 1 (#70c710)\n\
+            D 1 (#0dc571)\n\
+            R 1 (#5713f0)\n\
+            D 1 (#d2c081)\n\
+            L 2 (#5713f0)\n\
+            U 2 (#d2c081)";
+        let lines = plan.lines().collect_vec();
+        for zero in [
+            "R 0 (#000000)",
+            "D 0 (#000001)",
+            "L 0 (#000002)",
+            "U 0 (#000003)",
+        ] {
+            for i in 0..=lines.len() {
+                let mut with_zero = lines.clone();
+                with_zero.insert(i, zero);
+                let with_zero = with_zero.join("\n");
+                assert_eq!(part_1(&with_zero), part_1(plan), "{zero:?} at line {i}");
+                assert_eq!(part_2(&with_zero), part_2(plan), "{zero:?} at line {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_row_fill_counts() {
+        // #->
+        // ^ |
+        // <-v
+        let plan = "R 2 (#000020)\n\
+            D 2 (#000021)\n\
+            L 2 (#000022)\n\
+            U 2 (#000023)";
+        assert_eq!(row_fill_counts(plan, false), vec![(0, 3), (1, 3), (2, 3)]);
+        assert_eq!(row_fill_counts(plan, true), vec![(0, 3), (1, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn test_interior_area() {
+        let plan = "R 2 (#000020)\n\
+            D 2 (#000021)\n\
+            L 2 (#000022)\n\
+            U 2 (#000023)";
+        assert_eq!(interior_area(plan, false), part_1(plan) - 8);
+        assert_eq!(interior_area(plan, true), 1);
+    }
 }
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, BufRead},
+};
 
 use itertools::Itertools;
 
@@ -6,13 +10,29 @@ use itertools::Itertools;
 enum Operator {
     Gt,
     Lt,
+    Ge,
+    Le,
 }
 
-impl From<char> for Operator {
-    fn from(value: char) -> Self {
-        match value {
-            '>' => Operator::Gt,
-            '<' => Operator::Lt,
+impl Operator {
+    /// Number of characters used to write this operator
+    fn width(&self) -> usize {
+        match self {
+            Operator::Gt | Operator::Lt => 1,
+            Operator::Ge | Operator::Le => 2,
+        }
+    }
+}
+
+impl From<&str> for Operator {
+    /// Parse the operator at the start of the given string
+    fn from(value: &str) -> Self {
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some('>'), Some('=')) => Operator::Ge,
+            (Some('<'), Some('=')) => Operator::Le,
+            (Some('>'), _) => Operator::Gt,
+            (Some('<'), _) => Operator::Lt,
             _ => panic!(),
         }
     }
@@ -49,11 +69,11 @@ struct Condition {
 impl From<&str> for Condition {
     fn from(value: &str) -> Self {
         let prop = Property::from(value.chars().next().unwrap());
-        let op = Operator::from(value.chars().nth(1).unwrap());
+        let op = Operator::from(&value[1..]);
         let (temp, output_to) = value.split_once(':').unwrap();
         let compare_to = temp
             .chars()
-            .skip(2)
+            .skip(1 + op.width())
             .collect::<String>()
             .parse::<usize>()
             .unwrap();
@@ -92,15 +112,6 @@ enum Rule {
     Uncondition(Uncondition),
 }
 
-impl Rule {
-    fn get_output(&self) -> &str {
-        match self {
-            Rule::Condition(c) => &c.output_to,
-            Rule::Uncondition(u) => &u.output_to,
-        }
-    }
-}
-
 impl From<&str> for Rule {
     fn from(value: &str) -> Self {
         if let Ok(unc) = Uncondition::try_from(value) {
@@ -130,12 +141,12 @@ impl From<&str> for Workflow {
     }
 }
 
-#[derive(Debug, Clone)]
-struct Part {
-    x: usize,
-    m: usize,
-    a: usize,
-    s: usize,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub x: usize,
+    pub m: usize,
+    pub a: usize,
+    pub s: usize,
 }
 
 impl From<&str> for Part {
@@ -153,8 +164,114 @@ impl From<&str> for Part {
     }
 }
 
-impl PartialEq<Condition> for Part {
-    fn eq(&self, other: &Condition) -> bool {
+/// Where a rule sends the parts that match it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Destination {
+    Accept,
+    Reject,
+    Workflow(usize),
+}
+
+/// The comparison made by a conditional rule
+#[derive(Debug, Clone, Copy)]
+struct Check {
+    prop: Property,
+    op: Operator,
+    compare_to: usize,
+}
+
+/// A rule whose output refers to a workflow by its index
+#[derive(Debug, Clone, Copy)]
+struct CompiledRule {
+    /// Comparison the part must pass, or `None` if the rule always matches
+    check: Option<Check>,
+    output: Destination,
+}
+
+/// The workflows from the input, with their names interned to indexes so
+/// that evaluation doesn't need to look up strings
+#[derive(Debug, Clone)]
+struct CompiledWorkflows {
+    /// Name of each workflow, matching the indexes of `entries`
+    names: Vec<String>,
+    /// Rules of each workflow
+    entries: Vec<Vec<CompiledRule>>,
+    /// Index of the `in` workflow
+    in_index: usize,
+}
+
+impl From<&str> for CompiledWorkflows {
+    /// Compile the workflows section of the given input
+    fn from(value: &str) -> Self {
+        let workflows = value
+            .split("\n\n")
+            .next()
+            .unwrap()
+            .lines()
+            .map_into::<Workflow>()
+            .collect_vec();
+        let names = workflows.iter().map(|w| w.name.clone()).collect_vec();
+        let indexes: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let destination = |name: &str| match name {
+            "A" => Destination::Accept,
+            "R" => Destination::Reject,
+            _ => Destination::Workflow(indexes[name]),
+        };
+
+        let entries = workflows
+            .iter()
+            .map(|w| {
+                w.rules
+                    .iter()
+                    .map(|rule| match rule {
+                        Rule::Condition(c) => CompiledRule {
+                            check: Some(Check {
+                                prop: c.prop,
+                                op: c.op,
+                                compare_to: c.compare_to,
+                            }),
+                            output: destination(&c.output_to),
+                        },
+                        Rule::Uncondition(u) => CompiledRule {
+                            check: None,
+                            output: destination(&u.output_to),
+                        },
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let in_index = indexes["in"];
+
+        CompiledWorkflows {
+            names,
+            entries,
+            in_index,
+        }
+    }
+}
+
+impl CompiledWorkflows {
+    /// Follow the workflows from `in` to find where the part ends up
+    fn sort_part(&self, part: &Part) -> Destination {
+        let mut curr = Destination::Workflow(self.in_index);
+        while let Destination::Workflow(i) = curr {
+            curr = self.entries[i]
+                .iter()
+                .find(|rule| *part == **rule)
+                .unwrap()
+                .output;
+        }
+        curr
+    }
+}
+
+impl PartialEq<Check> for Part {
+    fn eq(&self, other: &Check) -> bool {
         let prop_to_check = match other.prop {
             Property::X => self.x,
             Property::M => self.m,
@@ -165,21 +282,17 @@ impl PartialEq<Condition> for Part {
         match other.op {
             Operator::Gt => prop_to_check > other.compare_to,
             Operator::Lt => prop_to_check < other.compare_to,
+            Operator::Ge => prop_to_check >= other.compare_to,
+            Operator::Le => prop_to_check <= other.compare_to,
         }
     }
 }
 
-impl PartialEq<Uncondition> for Part {
-    fn eq(&self, _: &Uncondition) -> bool {
-        true
-    }
-}
-
-impl PartialEq<Rule> for Part {
-    fn eq(&self, other: &Rule) -> bool {
-        match other {
-            Rule::Condition(c) => self == c,
-            Rule::Uncondition(u) => self == u,
+impl PartialEq<CompiledRule> for Part {
+    fn eq(&self, other: &CompiledRule) -> bool {
+        match &other.check {
+            Some(c) => self == c,
+            None => true,
         }
     }
 }
@@ -198,12 +311,23 @@ struct PartRange {
     s: (usize, usize),
 }
 
-impl From<PartRange> for usize {
-    fn from(value: PartRange) -> Self {
-        (value.x.1 - value.x.0 + 1)
-            * (value.m.1 - value.m.0 + 1)
-            * (value.a.1 - value.a.0 + 1)
-            * (value.s.1 - value.s.0 + 1)
+impl PartRange {
+    /// Number of distinct parts within the range
+    fn volume(&self) -> usize {
+        (self.x.1 - self.x.0 + 1)
+            * (self.m.1 - self.m.0 + 1)
+            * (self.a.1 - self.a.0 + 1)
+            * (self.s.1 - self.s.0 + 1)
+    }
+}
+
+impl Display for PartRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "x:{}-{} m:{}-{} a:{}-{} s:{}-{}",
+            self.x.0, self.x.1, self.m.0, self.m.1, self.a.0, self.a.1, self.s.0, self.s.1
+        )
     }
 }
 
@@ -238,38 +362,74 @@ fn narrow_range_to(range: (usize, usize), op: Operator, val: usize) -> Option<(u
                 Some((range.0, val - 1))
             }
         }
+        Operator::Ge => {
+            if range.0 >= val {
+                Some(range)
+            } else if range.1 < val {
+                None
+            } else {
+                Some((val, range.1))
+            }
+        }
+        Operator::Le => {
+            if range.1 <= val {
+                Some(range)
+            } else if range.0 > val {
+                None
+            } else {
+                Some((range.0, val))
+            }
+        }
     }
 }
 
 fn narrow_range_against(range: (usize, usize), op: Operator, val: usize) -> Option<(usize, usize)> {
     match op {
         Operator::Gt => {
-            if range.1 < val {
+            if range.1 <= val {
                 Some(range)
-            } else if range.0 >= val {
+            } else if range.0 > val {
                 None
             } else {
                 Some((range.0, val))
             }
         }
         Operator::Lt => {
+            if range.0 >= val {
+                Some(range)
+            } else if range.1 < val {
+                None
+            } else {
+                Some((val, range.1))
+            }
+        }
+        Operator::Ge => {
+            if range.1 < val {
+                Some(range)
+            } else if range.0 >= val {
+                None
+            } else {
+                Some((range.0, val - 1))
+            }
+        }
+        Operator::Le => {
             if range.0 > val {
                 Some(range)
             } else if range.1 <= val {
                 None
             } else {
-                Some((val, range.1))
+                Some((val + 1, range.1))
             }
         }
     }
 }
 
 impl PartRange {
-    fn narrow_to(&self, rule: &Rule) -> Option<PartRange> {
+    fn narrow_to(&self, rule: &CompiledRule) -> Option<PartRange> {
         let mut clone = self.clone();
-        match rule {
-            Rule::Uncondition(_) => Some(clone),
-            Rule::Condition(c) => {
+        match rule.check {
+            None => Some(clone),
+            Some(c) => {
                 match c.prop {
                     Property::X => {
                         clone.x = narrow_range_to(clone.x, c.op, c.compare_to)?;
@@ -289,11 +449,11 @@ impl PartRange {
         }
     }
 
-    fn narrow_against(&self, rule: &Rule) -> Option<PartRange> {
+    fn narrow_against(&self, rule: &CompiledRule) -> Option<PartRange> {
         let mut clone = self.clone();
-        match rule {
-            Rule::Uncondition(_) => None,
-            Rule::Condition(c) => {
+        match rule.check {
+            None => None,
+            Some(c) => {
                 match c.prop {
                     Property::X => {
                         clone.x = narrow_range_against(clone.x, c.op, c.compare_to)?;
@@ -314,141 +474,273 @@ impl PartRange {
     }
 }
 
-#[aoc(day19, part1)]
-pub fn part_1(input: &str) -> usize {
-    let (workflows, parts) = input.split_once("\n\n").unwrap();
-
-    let workflows: HashMap<String, Vec<Rule>> = workflows
-        .lines()
-        .map_into::<Workflow>()
-        .map(|w| (w.name, w.rules))
-        .collect();
+/// Returns each part in the input, along with whether it is accepted
+pub fn verdicts(input: &str) -> Vec<(Part, bool)> {
+    let workflows = CompiledWorkflows::from(input);
+    let parts = input.split_once("\n\n").unwrap().1;
 
     parts
         .lines()
         .map_into::<Part>()
         .map(|part| {
-            let mut curr_workflow = "in";
-            while !["A", "R"].contains(&curr_workflow) {
-                for rule in workflows.get(curr_workflow).unwrap() {
-                    if part == *rule {
-                        curr_workflow = rule.get_output();
-                        break;
-                    }
-                }
-            }
-
-            if curr_workflow == "A" {
-                usize::from(part)
-            } else {
-                0
-            }
+            let accepted = workflows.sort_part(&part) == Destination::Accept;
+            (part, accepted)
         })
+        .collect_vec()
+}
+
+#[aoc(day19, part1)]
+pub fn part_1(input: &str) -> usize {
+    verdicts(input)
+        .into_iter()
+        .filter(|(_, accepted)| *accepted)
+        .map(|(part, _)| usize::from(part))
         .sum()
 }
 
+/// Solve part 1, reading the parts one line at a time from `parts` so that
+/// they never all need to be in memory at once
+pub fn part_1_reader<R: BufRead>(workflows: &str, parts: R) -> io::Result<usize> {
+    let workflows = CompiledWorkflows::from(workflows);
+
+    let mut total = 0;
+    for line in parts.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let part = Part::from(line.as_str());
+        if workflows.sort_part(&part) == Destination::Accept {
+            total += usize::from(part);
+        }
+    }
+
+    Ok(total)
+}
+
+/// Push each sub-range of the given range that ends up at the `target`
+/// terminal destination (either accepted or rejected) onto `found`
+fn find_ranges(
+    workflows: &CompiledWorkflows,
+    curr: Destination,
+    part_range: &PartRange,
+    target: Destination,
+    found: &mut Vec<PartRange>,
+) {
+    let Destination::Workflow(index) = curr else {
+        if curr == target {
+            found.push(part_range.clone());
+        }
+        return;
+    };
+
+    let mut remaining = Some(part_range.clone());
+    for rule in &workflows.entries[index] {
+        let Some(range) = remaining else {
+            break;
+        };
+        if let Some(narrowed) = range.narrow_to(rule) {
+            find_ranges(workflows, rule.output, &narrowed, target, found);
+        }
+        remaining = range.narrow_against(rule);
+    }
+}
+
+/// Count the parts in the given range that end up at the `target` terminal
+/// destination (either accepted or rejected)
 fn determine_num_parts(
-    workflows: &HashMap<String, Vec<Rule>>,
-    curr_workflow: &str,
+    workflows: &CompiledWorkflows,
+    curr: Destination,
     part_range: &PartRange,
+    target: Destination,
 ) -> usize {
-    if curr_workflow == "A" {
-        usize::from(part_range.clone())
-    } else if curr_workflow == "R" {
-        0
-    } else {
-        workflows
-            .get(curr_workflow)
-            .unwrap()
-            .iter()
-            .fold(
-                (Some(part_range.clone()), 0),
-                |(part_range, acc), rule| match part_range {
-                    Some(range) => {
-                        let narrowed_to = range.narrow_to(rule);
-                        let num_parts_for_this_workflow = if let Some(narrowed) = narrowed_to {
-                            determine_num_parts(workflows, rule.get_output(), &narrowed)
-                        } else {
-                            0
-                        };
-                        let narrowed_against = range.narrow_against(rule);
-                        (narrowed_against, acc + num_parts_for_this_workflow)
-                    }
-                    None => (part_range, acc),
-                },
-            )
-            .1
-    }
+    let mut found = vec![];
+    find_ranges(workflows, curr, part_range, target, &mut found);
+    found.iter().map(PartRange::volume).sum()
 }
 
 #[aoc(day19, part2)]
 pub fn part_2(input: &str) -> usize {
-    let workflows: HashMap<String, Vec<Rule>> = input
-        .split_once("\n\n")
-        .unwrap()
-        .0
-        .lines()
-        .map_into::<Workflow>()
-        .map(|w| (w.name, w.rules))
-        .collect();
+    let workflows = CompiledWorkflows::from(input);
+
+    determine_num_parts(
+        &workflows,
+        Destination::Workflow(workflows.in_index),
+        &PartRange::default(),
+        Destination::Accept,
+    )
+}
+
+/// Count the parts that are rejected, which is the complement of part 2
+pub fn rejected_count(input: &str) -> usize {
+    let workflows = CompiledWorkflows::from(input);
+
+    determine_num_parts(
+        &workflows,
+        Destination::Workflow(workflows.in_index),
+        &PartRange::default(),
+        Destination::Reject,
+    )
+}
+
+/// Returns the accepted part with the lexicographically smallest `(x, m, a, s)`
+/// ratings, or `None` if no parts are accepted
+pub fn example_accepted(input: &str) -> Option<Part> {
+    let workflows = CompiledWorkflows::from(input);
+
+    let mut accepted = vec![];
+    find_ranges(
+        &workflows,
+        Destination::Workflow(workflows.in_index),
+        &PartRange::default(),
+        Destination::Accept,
+        &mut accepted,
+    );
+
+    // The smallest part in each range is its lower corner
+    accepted
+        .into_iter()
+        .map(|range| (range.x.0, range.m.0, range.a.0, range.s.0))
+        .min()
+        .map(|(x, m, a, s)| Part { x, m, a, s })
+}
 
-    determine_num_parts(&workflows, "in", &PartRange::default())
+/// How a workflow treats the full range of possible parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkflowClass {
+    /// Every part that reaches this workflow is eventually accepted
+    AlwaysAccept,
+    /// Every part that reaches this workflow is eventually rejected
+    AlwaysReject,
+    /// Some parts are accepted and others are rejected
+    Mixed,
+}
+
+/// Determine whether the given workflow always accepts, always rejects, or
+/// does a mix of both, by sending the full range of parts through it
+fn classify_workflow(workflows: &CompiledWorkflows, index: usize) -> WorkflowClass {
+    let all_parts = PartRange::default();
+    let num_accepted = determine_num_parts(
+        workflows,
+        Destination::Workflow(index),
+        &all_parts,
+        Destination::Accept,
+    );
+
+    if num_accepted == 0 {
+        WorkflowClass::AlwaysReject
+    } else if num_accepted == all_parts.volume() {
+        WorkflowClass::AlwaysAccept
+    } else {
+        WorkflowClass::Mixed
+    }
+}
+
+/// Classify every workflow in the input by how it treats the full range of
+/// parts
+pub fn classify_workflows(input: &str) -> HashMap<String, WorkflowClass> {
+    let workflows = CompiledWorkflows::from(input);
+
+    workflows
+        .names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.clone(), classify_workflow(&workflows, i)))
+        .collect()
+}
+
+/// Returns the names of the workflows that parts can be sent to starting from
+/// `in` (including `in` itself), sorted by name
+///
+/// Any workflows that aren't included can never be used.
+pub fn reachable_workflows(input: &str) -> Vec<String> {
+    let workflows = CompiledWorkflows::from(input);
+
+    let mut reached = vec![false; workflows.entries.len()];
+    reached[workflows.in_index] = true;
+    let mut stack = vec![workflows.in_index];
+
+    while let Some(i) = stack.pop() {
+        for rule in &workflows.entries[i] {
+            if let Destination::Workflow(next) = rule.output {
+                if !reached[next] {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    workflows
+        .names
+        .into_iter()
+        .zip(reached)
+        .filter(|(_, reached)| *reached)
+        .map(|(name, _)| name)
+        .sorted()
+        .collect_vec()
 }
 
 #[cfg(test)]
 mod test {
-    use super::{narrow_range_against, narrow_range_to, part_1, part_2, Operator};
+    use super::{
+        classify_workflows, example_accepted, narrow_range_against, narrow_range_to, part_1,
+        part_1_reader, part_2, reachable_workflows, rejected_count, verdicts, CompiledWorkflows,
+        Destination, Operator, Part, PartRange, WorkflowClass,
+    };
+    use itertools::Itertools;
+    use std::io::Cursor;
+
+    const SAMPLE: &str = "px{a<2006:qkq,m>2090:A,rfg}\n\
+        pv{a>1716:R,A}\n\
+        lnx{m>1548:A,A}\n\
+        rfg{s<537:gd,x>2440:R,A}\n\
+        qs{s>3448:A,lnx}\n\
+        qkq{x<1416:A,crn}\n\
+        crn{x>2662:A,R}\n\
+        in{s<1351:px,qqz}\n\
+        qqz{s>2770:qs,m<1801:hdj,R}\n\
+        gd{a>3333:R,R}\n\
+        hdj{m>838:A,pv}\n\
+        \n\
+        {x=787,m=2655,a=1222,s=2876}\n\
+        {x=1679,m=44,a=2067,s=496}\n\
+        {x=2036,m=264,a=79,s=2244}\n\
+        {x=2461,m=1339,a=466,s=291}\n\
+        {x=2127,m=1623,a=2188,s=1013}";
 
     #[test]
     fn test_part_1() {
-        assert_eq!(
-            part_1(
-                "px{a<2006:qkq,m>2090:A,rfg}\n\
-                pv{a>1716:R,A}\n\
-                lnx{m>1548:A,A}\n\
-                rfg{s<537:gd,x>2440:R,A}\n\
-                qs{s>3448:A,lnx}\n\
-                qkq{x<1416:A,crn}\n\
-                crn{x>2662:A,R}\n\
-                in{s<1351:px,qqz}\n\
-                qqz{s>2770:qs,m<1801:hdj,R}\n\
-                gd{a>3333:R,R}\n\
-                hdj{m>838:A,pv}\n\
-                \n\
-                {x=787,m=2655,a=1222,s=2876}\n\
-                {x=1679,m=44,a=2067,s=496}\n\
-                {x=2036,m=264,a=79,s=2244}\n\
-                {x=2461,m=1339,a=466,s=291}\n\
-                {x=2127,m=1623,a=2188,s=1013}"
-            ),
-            19114
-        )
+        assert_eq!(part_1(SAMPLE), 19114)
+    }
+
+    #[test]
+    fn test_part_1_reader() {
+        let (workflows, parts) = SAMPLE.split_once("\n\n").unwrap();
+        assert_eq!(part_1_reader(workflows, Cursor::new(parts)).unwrap(), 19114)
     }
 
     #[test]
     fn test_part_2() {
+        assert_eq!(part_2(SAMPLE), 167409079868000)
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let verdicts = verdicts(SAMPLE);
         assert_eq!(
-            part_2(
-                "px{a<2006:qkq,m>2090:A,rfg}\n\
-                pv{a>1716:R,A}\n\
-                lnx{m>1548:A,A}\n\
-                rfg{s<537:gd,x>2440:R,A}\n\
-                qs{s>3448:A,lnx}\n\
-                qkq{x<1416:A,crn}\n\
-                crn{x>2662:A,R}\n\
-                in{s<1351:px,qqz}\n\
-                qqz{s>2770:qs,m<1801:hdj,R}\n\
-                gd{a>3333:R,R}\n\
-                hdj{m>838:A,pv}\n\
-                \n\
-                {x=787,m=2655,a=1222,s=2876}\n\
-                {x=1679,m=44,a=2067,s=496}\n\
-                {x=2036,m=264,a=79,s=2244}\n\
-                {x=2461,m=1339,a=466,s=291}\n\
-                {x=2127,m=1623,a=2188,s=1013}"
-            ),
-            167409079868000
-        )
+            verdicts.iter().map(|(_, accepted)| *accepted).collect_vec(),
+            [true, false, true, false, true]
+        );
+        assert_eq!(
+            verdicts[0].0,
+            Part {
+                x: 787,
+                m: 2655,
+                a: 1222,
+                s: 2876
+            }
+        );
     }
 
     #[test]
@@ -495,6 +787,25 @@ mod test {
         assert_eq!(narrow_range_against((0, 4000), Operator::Lt, 4001), None);
     }
 
+    #[test]
+    fn test_narrow_range_boundaries() {
+        // A single value on the boundary of the comparison
+        assert_eq!(narrow_range_to((5, 5), Operator::Gt, 5), None);
+        assert_eq!(narrow_range_against((5, 5), Operator::Gt, 5), Some((5, 5)));
+        assert_eq!(narrow_range_to((5, 5), Operator::Lt, 5), None);
+        assert_eq!(narrow_range_against((5, 5), Operator::Lt, 5), Some((5, 5)));
+
+        // A single value just inside the comparison
+        assert_eq!(narrow_range_to((5, 5), Operator::Gt, 4), Some((5, 5)));
+        assert_eq!(narrow_range_against((5, 5), Operator::Gt, 4), None);
+        assert_eq!(narrow_range_to((5, 5), Operator::Lt, 6), Some((5, 5)));
+        assert_eq!(narrow_range_against((5, 5), Operator::Lt, 6), None);
+
+        // Ranges that start or end on the compared value
+        assert_eq!(narrow_range_against((5, 10), Operator::Gt, 5), Some((5, 5)));
+        assert_eq!(narrow_range_against((1, 5), Operator::Lt, 5), Some((5, 5)));
+    }
+
     #[test]
     fn test_part_2_simple() {
         assert_eq!(
@@ -506,4 +817,108 @@ mod test {
             4000 * 4000 * 4000 * 3001
         )
     }
+
+    #[test]
+    fn test_classify_workflows() {
+        let classes = classify_workflows(
+            "in{m<10:a,s>20:r,x<5:A,R}\n\
+            a{x>0:A,A}\n\
+            r{a<100:R,R}\n\
+            \n\
+            {x=2127,m=1623,a=2188,s=1013}",
+        );
+        assert_eq!(classes["a"], WorkflowClass::AlwaysAccept);
+        assert_eq!(classes["r"], WorkflowClass::AlwaysReject);
+        assert_eq!(classes["in"], WorkflowClass::Mixed);
+    }
+
+    #[test]
+    fn test_inclusive_operators() {
+        assert_eq!(
+            narrow_range_to((1, 4000), Operator::Ge, 2006),
+            Some((2006, 4000))
+        );
+        assert_eq!(
+            narrow_range_against((1, 4000), Operator::Ge, 2006),
+            Some((1, 2005))
+        );
+        assert_eq!(
+            narrow_range_to((1, 4000), Operator::Le, 2006),
+            Some((1, 2006))
+        );
+        assert_eq!(
+            narrow_range_against((1, 4000), Operator::Le, 2006),
+            Some((2007, 4000))
+        );
+        assert_eq!(narrow_range_to((1, 2005), Operator::Ge, 2006), None);
+        assert_eq!(narrow_range_against((2006, 4000), Operator::Ge, 2006), None);
+    }
+
+    #[test]
+    fn test_inclusive_workflow() {
+        let input = "in{a>=2006:A,R}\n\
+            \n\
+            {x=1,m=1,a=2006,s=1}\n\
+            {x=1,m=1,a=2005,s=1}";
+        assert_eq!(part_1(input), 2009);
+        assert_eq!(part_2(input), 4000 * 4000 * 4000 * 1995);
+    }
+
+    #[test]
+    fn test_rejected_count() {
+        assert_eq!(part_2(SAMPLE) + rejected_count(SAMPLE), 4000usize.pow(4));
+    }
+
+    #[test]
+    fn test_display_part_range() {
+        let range = PartRange::default();
+        assert_eq!(range.to_string(), "x:1-4000 m:1-4000 a:1-4000 s:1-4000");
+        assert_eq!(range.volume(), 4000usize.pow(4));
+    }
+
+    #[test]
+    fn test_compiled_workflows() {
+        let workflows = CompiledWorkflows::from(SAMPLE);
+        assert_eq!(workflows.entries.len(), 11);
+        assert_eq!(workflows.names[workflows.in_index], "in");
+
+        // in{s<1351:px,qqz}
+        let px = workflows.names.iter().position(|n| n == "px").unwrap();
+        let in_rules = &workflows.entries[workflows.in_index];
+        assert_eq!(in_rules[0].output, Destination::Workflow(px));
+
+        assert_eq!(part_1(SAMPLE), 19114);
+        assert_eq!(part_2(SAMPLE), 167409079868000);
+    }
+
+    #[test]
+    fn test_example_accepted() {
+        let part = example_accepted(SAMPLE).unwrap();
+        let workflows = CompiledWorkflows::from(SAMPLE);
+        assert_eq!(workflows.sort_part(&part), Destination::Accept);
+        assert_eq!(
+            part,
+            Part {
+                x: 1,
+                m: 1,
+                a: 1,
+                s: 1
+            }
+        );
+
+        assert_eq!(example_accepted("in{x>0:R,A}\n\n{x=1,m=1,a=1,s=1}"), None);
+    }
+
+    #[test]
+    fn test_reachable_workflows() {
+        let input = "in{x>10:a,R}\n\
+            a{m<5:A,b}\n\
+            b{A}\n\
+            zzz{s>1:b,R}\n\
+            \n\
+            {x=787,m=2655,a=1222,s=2876}";
+        assert_eq!(reachable_workflows(input), ["a", "b", "in"]);
+        // Every workflow in the sample is used
+        assert_eq!(reachable_workflows(SAMPLE).len(), 11);
+    }
 }
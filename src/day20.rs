@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::{Debug, Display},
     iter::Sum,
     ops::{Add, AddAssign, Mul, Not},
@@ -10,7 +10,7 @@ use itertools::Itertools;
 type ModuleId = usize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum Pulse {
+pub enum Pulse {
     High,
     Low,
 }
@@ -39,12 +39,25 @@ impl Display for Pulse {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct PulseAtTime(usize, Pulse);
 
-#[derive(Debug, Clone, Copy, Default)]
-struct PulseCounter {
+/// Number of high and low pulses sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PulseCounter {
     high: usize,
     low: usize,
 }
 
+impl PulseCounter {
+    /// Number of high pulses counted
+    pub fn high(&self) -> usize {
+        self.high
+    }
+
+    /// Number of low pulses counted
+    pub fn low(&self) -> usize {
+        self.low
+    }
+}
+
 impl Add<Pulse> for PulseCounter {
     type Output = PulseCounter;
 
@@ -105,11 +118,17 @@ struct Event {
     from: ModuleId,
     target: ModuleId,
     pulse: Pulse,
+    /// Button press that caused the event, counting from 1
+    press: usize,
 }
 
 #[derive(Debug, Clone, Default)]
 struct EventQueue {
     queue: VecDeque<Event>,
+    /// Number of times the button has been pressed
+    press: usize,
+    /// Every event that has been processed, if they are being recorded
+    history: Option<Vec<Event>>,
 }
 
 impl EventQueue {
@@ -118,9 +137,16 @@ impl EventQueue {
             from,
             target,
             pulse,
+            press: self.press,
         });
     }
 
+    /// Press the button, sending a low pulse to the broadcaster
+    fn press_button(&mut self, broadcaster_id: ModuleId) {
+        self.press += 1;
+        self.push(broadcaster_id, broadcaster_id, Pulse::Low);
+    }
+
     fn pop(&mut self) -> Option<Event> {
         self.queue.pop_front()
     }
@@ -129,11 +155,29 @@ impl EventQueue {
     //     self.queue.is_empty()
     // }
 
-    fn drain(&mut self, modules: &mut [Module]) {
+    /// Process events until the queue is empty, returning the number of
+    /// events that were processed
+    fn drain(&mut self, modules: &mut [Module]) -> usize {
+        self.drain_bounded(modules, usize::MAX).unwrap()
+    }
+
+    /// Process events until the queue is empty, returning the number of
+    /// events that were processed, or `None` if more than `max_events` would
+    /// need to be processed, as happens when modules oscillate forever
+    fn drain_bounded(&mut self, modules: &mut [Module], max_events: usize) -> Option<usize> {
+        let mut num_events = 0;
         while let Some(e) = self.pop() {
+            if num_events == max_events {
+                return None;
+            }
             // println!("{} {}-> {}", modules[e.from], e.pulse, modules[e.target]);
             modules[e.target].receive_pulse(e.from, e.pulse, self);
+            if let Some(history) = &mut self.history {
+                history.push(e);
+            }
+            num_events += 1;
         }
+        Some(num_events)
     }
 }
 
@@ -472,7 +516,7 @@ enum ModuleVariant {
 }
 
 #[derive(Debug, Clone)]
-struct Module {
+pub struct Module {
     name: String,
     variant: ModuleVariant,
     counts: PulseCounter,
@@ -534,6 +578,11 @@ impl Module {
     fn is_broadcaster(&self) -> bool {
         matches!(self.variant, ModuleVariant::Broadcaster(_))
     }
+
+    /// Returns whether the module is a flip-flop that is switched on
+    pub fn is_on(&self) -> bool {
+        matches!(&self.variant, ModuleVariant::FlipFlop(f) if f.state == Pulse::High)
+    }
 }
 
 impl ModuleTrait for Module {
@@ -602,11 +651,18 @@ impl ModuleTrait for Module {
     }
 }
 
+/// Assign an ID to each module, with the broadcaster first, followed by the
+/// remaining modules in order of their names
+///
+/// This means the IDs don't depend on the order of the lines in the input, so
+/// the modules are always set up the same way.
 fn make_mod_names_map(names: &str) -> HashMap<String, ModuleId> {
     names
         .lines()
+        .map(extract_module_name_from_line)
+        .sorted_by(|a, b| (a != "broadcaster", a).cmp(&(b != "broadcaster", b)))
         .enumerate()
-        .map(|(i, line)| (extract_module_name_from_line(line), i))
+        .map(|(i, name)| (name, i))
         .collect()
 }
 
@@ -623,7 +679,8 @@ fn set_up_modules(input: &str) -> Vec<Module> {
 
     let mut modules = input
         .lines()
-        .enumerate()
+        .map(|line| (mod_names[&extract_module_name_from_line(line)], line))
+        .sorted()
         .map(|(id, line)| Module::new(id, line, &mod_names))
         .collect_vec();
 
@@ -644,6 +701,64 @@ fn set_up_modules(input: &str) -> Vec<Module> {
     modules
 }
 
+/// Check that each line of the input is a valid module definition, such as
+/// `%a -> b, c`, so that setting up the modules won't panic
+fn validate_input(input: &str) -> Result<(), String> {
+    let is_valid_name = |name: &str| !name.is_empty() && name.chars().all(char::is_alphanumeric);
+
+    for (i, line) in input.lines().enumerate() {
+        let line_num = i + 1;
+        let Some((module, outputs)) = line.split_once(" -> ") else {
+            return Err(format!("Line {line_num} ({line:?}) is missing \" -> \""));
+        };
+
+        let name = module.strip_prefix(['%', '&']).unwrap_or(module);
+        if !is_valid_name(name) || (name == module && name != "broadcaster") {
+            return Err(format!(
+                "Line {line_num} ({line:?}) has invalid module {module:?}, expected \
+                \"broadcaster\" or a name prefixed with '%' or '&'"
+            ));
+        }
+
+        if !outputs.is_empty() {
+            if let Some(output) = outputs.split(", ").find(|o| !is_valid_name(o)) {
+                return Err(format!(
+                    "Line {line_num} ({line:?}) has invalid output {output:?}"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Names of modules that only receive pulses, and so don't need to be defined
+/// in the input
+const SINKS: [&str; 2] = ["rx", "output"];
+
+/// Like `set_up_modules`, but give an error if any module outputs to a module
+/// that isn't defined in the input or one of the known sinks, rather than
+/// silently sending it to the debug module
+pub fn set_up_modules_strict(input: &str) -> Result<Vec<Module>, String> {
+    validate_input(input)?;
+
+    let mod_names = make_mod_names_map(input);
+
+    for line in input.lines() {
+        let outputs = line.split_once(" -> ").unwrap().1;
+        for output in outputs.split(", ").filter(|o| !o.is_empty()) {
+            if !mod_names.contains_key(output) && !SINKS.contains(&output) {
+                return Err(format!(
+                    "Module {:?} outputs to unknown module {output:?}",
+                    extract_module_name_from_line(line)
+                ));
+            }
+        }
+    }
+
+    Ok(set_up_modules(input))
+}
+
 fn find_broadcaster_module(modules: &[Module]) -> ModuleId {
     modules
         .iter()
@@ -658,6 +773,15 @@ fn find_with_name(modules: &[Module], name: &str) -> ModuleId {
 
 #[aoc(day20, part1)]
 pub fn part_1(input: &str) -> usize {
+    try_part_1(input, usize::MAX).unwrap()
+}
+
+/// Like `part_1`, but give an error rather than panicking if the input is
+/// invalid, or hanging if any button press causes more than `max_events`
+/// pulses to be sent
+pub fn try_part_1(input: &str, max_events: usize) -> Result<usize, String> {
+    validate_input(input)?;
+
     let mut modules = set_up_modules(input);
 
     let broadcaster_id = find_broadcaster_module(&modules);
@@ -666,9 +790,20 @@ pub fn part_1(input: &str) -> usize {
 
     let mut push_count = 0;
 
+    let mut press = |modules: &mut [Module], push_count: usize| {
+        event_queue.press_button(broadcaster_id);
+        event_queue
+            .drain_bounded(modules, max_events)
+            .ok_or_else(|| {
+                format!(
+                    "Button press {} exceeded the limit of {max_events} pulses",
+                    push_count + 1
+                )
+            })
+    };
+
     while push_count == 0 || !modules.iter().all(|m| m.is_in_initial_state()) {
-        event_queue.push(broadcaster_id, broadcaster_id, Pulse::Low);
-        event_queue.drain(&mut modules);
+        press(&mut modules, push_count)?;
         push_count += 1;
         if push_count == 1000 {
             break;
@@ -680,18 +815,35 @@ pub fn part_1(input: &str) -> usize {
     let num_cycles = 1000 / push_count;
     let remaining_pushes = 1000 - num_cycles * push_count;
 
-    for _ in 0..remaining_pushes {
-        event_queue.push(broadcaster_id, broadcaster_id, Pulse::Low);
-        event_queue.drain(&mut modules);
+    for i in 0..remaining_pushes {
+        press(&mut modules, num_cycles * push_count + i)?;
     }
 
-    usize::from(
+    Ok(usize::from(
         modules
             .iter()
             .map(|m| m.get_pulse_counts())
             .sum::<PulseCounter>()
             + pulses_per_cycle * (num_cycles - 1),
-    )
+    ))
+}
+
+/// Solve part 1 by simply pressing the button 1000 times, without trying to
+/// find a cycle, to give a reference for the faster approach
+pub fn part_1_brute_force(input: &str) -> usize {
+    usize::from(pulse_counts_after(input, 1000))
+}
+
+/// The number of button presses `part_2` tries before giving up, if it needs to
+/// fall back to brute force
+const BRUTE_FORCE_PRESSES: usize = 1_000_000;
+
+/// Returns whether any module can send pulses back around to itself
+fn has_loop(input: &str) -> bool {
+    input
+        .lines()
+        .map(extract_module_name_from_line)
+        .any(|name| ancestors(input, &name).contains(&name))
 }
 
 #[aoc(day20, part2)]
@@ -699,6 +851,12 @@ pub fn part_2(input: &str) -> usize {
     // unsafe {
     //     backtrace_on_stack_overflow::enable();
     // }
+    if has_loop(input) {
+        // Working out the periods follows the inputs of each module back
+        // towards the broadcaster, which never ends if pulses can loop around
+        return part_2_brute_force(input, BRUTE_FORCE_PRESSES).unwrap();
+    }
+
     let modules = set_up_modules(input);
     let rx_id = find_with_name(&modules, "debug");
 
@@ -717,7 +875,14 @@ pub fn part_2(input: &str) -> usize {
         + 1
 }
 
-pub fn part_2_brute_force(input: &str) -> usize {
+/// Solve part 2 by pressing the button until the sink first receives a low
+/// pulse, without any of the period analysis that `part_2` uses
+///
+/// This is far too slow for the real input, but is simple enough to trust, so
+/// is used to check `part_2` on small graphs. Gives an error if the sink still
+/// hasn't received a low pulse after `max_presses` presses, since for some
+/// graphs it never will.
+pub fn part_2_brute_force(input: &str, max_presses: usize) -> Result<usize, String> {
     let mut modules = set_up_modules(input);
 
     let broadcaster_id = find_broadcaster_module(&modules);
@@ -728,7 +893,12 @@ pub fn part_2_brute_force(input: &str) -> usize {
     let mut push_count = 0;
 
     while modules[rx_id].counts.low == 0 {
-        event_queue.push(broadcaster_id, broadcaster_id, Pulse::Low);
+        if push_count == max_presses {
+            return Err(format!(
+                "No low pulse reached the sink within {max_presses} button presses"
+            ));
+        }
+        event_queue.press_button(broadcaster_id);
         event_queue.drain(&mut modules);
         push_count += 1;
         // if push_count % 1_000_000 == 0 {
@@ -736,14 +906,166 @@ pub fn part_2_brute_force(input: &str) -> usize {
         // }
     }
 
-    push_count
+    Ok(push_count)
 }
 
+/// Returns the first button press (counting from 1) during which the module
+/// named `module` satisfies the `predicate`, or `None` if that doesn't happen
+/// within `max` presses
+///
+/// The predicate is checked after every pulse, so states that only last part
+/// of the way through a press are still found.
+pub fn presses_until_state(
+    input: &str,
+    module: &str,
+    predicate: impl Fn(&Module) -> bool,
+    max: usize,
+) -> Option<usize> {
+    let mut modules = set_up_modules(input);
+
+    let broadcaster_id = find_broadcaster_module(&modules);
+    let module_id = find_with_name(&modules, module);
 
+    let mut event_queue = EventQueue::default();
+
+    for press in 1..=max {
+        event_queue.press_button(broadcaster_id);
+        while let Some(e) = event_queue.pop() {
+            modules[e.target].receive_pulse(e.from, e.pulse, &mut event_queue);
+            if predicate(&modules[module_id]) {
+                return Some(press);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the number of pulses sent as a result of each button press
+/// (including the pulse from the button to the broadcaster)
+pub fn cascade_sizes(input: &str, presses: usize) -> Vec<usize> {
+    let mut modules = set_up_modules(input);
+
+    let broadcaster_id = find_broadcaster_module(&modules);
+
+    let mut event_queue = EventQueue::default();
+
+    (0..presses)
+        .map(|_| {
+            event_queue.press_button(broadcaster_id);
+            event_queue.drain(&mut modules)
+        })
+        .collect_vec()
+}
+
+/// Returns each pulse sent from the module named `from` to the module named
+/// `to` over the given number of button presses, along with the number of the
+/// press that caused it (counting from 1)
+///
+/// Outputs that aren't defined in the input are all named `debug`.
+pub fn edge_pulses(input: &str, from: &str, to: &str, presses: usize) -> Vec<(usize, Pulse)> {
+    let mut modules = set_up_modules(input);
+
+    let broadcaster_id = find_broadcaster_module(&modules);
+    let from_id = find_with_name(&modules, from);
+    let to_id = find_with_name(&modules, to);
+
+    let mut event_queue = EventQueue {
+        history: Some(vec![]),
+        ..Default::default()
+    };
+
+    for _ in 0..presses {
+        event_queue.press_button(broadcaster_id);
+        event_queue.drain(&mut modules);
+    }
+
+    event_queue
+        .history
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.from == from_id && e.target == to_id)
+        .map(|e| (e.press, e.pulse))
+        .collect_vec()
+}
+
+/// Returns the names of the modules defined in the input, grouped by their
+/// type as `(flip_flops, conjunctions, broadcasters)`, each in input order
+pub fn modules_by_type(input: &str) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut flip_flops = vec![];
+    let mut conjunctions = vec![];
+    let mut broadcasters = vec![];
+
+    for line in input.lines() {
+        let name = extract_module_name_from_line(line);
+        match line.chars().next().unwrap() {
+            '%' => flip_flops.push(name),
+            '&' => conjunctions.push(name),
+            _ => broadcasters.push(name),
+        }
+    }
+
+    (flip_flops, conjunctions, broadcasters)
+}
+
+/// Returns the names of all the modules that can send pulses to the module
+/// named `target`, either directly or through other modules, sorted by name
+///
+/// Unlike the other functions, this works on names, so outputs that aren't
+/// defined in the input (such as `rx`) can be used as the target.
+pub fn ancestors(input: &str, target: &str) -> Vec<String> {
+    let mut inputs: HashMap<String, Vec<String>> = HashMap::new();
+    for line in input.lines() {
+        let name = extract_module_name_from_line(line);
+        let outputs = line.split_once(" -> ").unwrap().1;
+        for output in outputs.split(", ").filter(|o| !o.is_empty()) {
+            inputs
+                .entry(output.to_owned())
+                .or_default()
+                .push(name.clone());
+        }
+    }
+
+    // Search backwards from the target
+    let mut found = HashSet::new();
+    let mut queue = VecDeque::from([target.to_owned()]);
+    while let Some(module) = queue.pop_front() {
+        for input in inputs.get(&module).into_iter().flatten() {
+            if found.insert(input.clone()) {
+                queue.push_back(input.clone());
+            }
+        }
+    }
+
+    found.into_iter().sorted().collect_vec()
+}
+
+/// Returns the total number of pulses sent after pressing the button the
+/// given number of times
+pub fn pulse_counts_after(input: &str, presses: usize) -> PulseCounter {
+    let mut modules = set_up_modules(input);
+
+    let broadcaster_id = find_broadcaster_module(&modules);
+
+    let mut event_queue = EventQueue::default();
+
+    for _ in 0..presses {
+        event_queue.press_button(broadcaster_id);
+        event_queue.drain(&mut modules);
+    }
+
+    modules.iter().map(|m| m.get_pulse_counts()).sum()
+}
 
 #[cfg(test)]
 mod test {
-    use super::{part_1, part_2, part_2_brute_force};
+    use super::{
+        ancestors, cascade_sizes, edge_pulses, has_loop, modules_by_type, part_1,
+        part_1_brute_force, part_2, part_2_brute_force, presses_until_state, pulse_counts_after,
+        set_up_modules, set_up_modules_strict, try_part_1, validate_input, Module, ModuleTrait,
+        Pulse, PulseCounter,
+    };
+    use itertools::Itertools;
 
     #[test]
     fn test_part_1_simple() {
@@ -759,6 +1081,178 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_line_order_doesnt_matter() {
+        let input = "broadcaster -> a, b, c\n\
+            %a -> b\n\
+            %b -> c\n\
+            %c -> inv\n\
+            &inv -> a";
+        let shuffled = "%c -> inv\n\
+            &inv -> a\n\
+            %b -> c\n\
+            broadcaster -> a, b, c\n\
+            %a -> b";
+        assert_eq!(part_1(shuffled), part_1(input));
+        assert_eq!(
+            set_up_modules(shuffled)
+                .iter()
+                .map(|m| &m.name)
+                .collect_vec(),
+            ["broadcaster", "a", "b", "c", "inv", "debug"]
+        );
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let input = "broadcaster -> a\n\
+            %a -> inv, con\n\
+            &inv -> b\n\
+            %b -> con\n\
+            &con -> output";
+        assert_eq!(
+            ancestors(input, "output"),
+            ["a", "b", "broadcaster", "con", "inv"]
+        );
+        assert_eq!(ancestors(input, "inv"), ["a", "broadcaster"]);
+        assert!(ancestors(input, "broadcaster").is_empty());
+    }
+
+    #[test]
+    fn test_modules_by_type() {
+        let (flip_flops, conjunctions, broadcasters) = modules_by_type(
+            "broadcaster -> a\n\
+            %a -> inv, con\n\
+            &inv -> b\n\
+            %b -> con\n\
+            &con -> output",
+        );
+        assert_eq!(flip_flops, ["a", "b"]);
+        assert_eq!(conjunctions, ["inv", "con"]);
+        assert_eq!(broadcasters, ["broadcaster"]);
+    }
+
+    #[test]
+    fn test_presses_until_state() {
+        let simple = "broadcaster -> a, b, c\n\
+            %a -> b\n\
+            %b -> c\n\
+            %c -> inv\n\
+            &inv -> a";
+        // The flip-flops are switched on partway through the first press,
+        // even though they are all off again by the end of it
+        assert_eq!(presses_until_state(simple, "c", Module::is_on, 10), Some(1));
+        assert_eq!(presses_until_state(simple, "inv", Module::is_on, 10), None);
+
+        let advanced = "broadcaster -> a\n\
+            %a -> inv, con\n\
+            &inv -> b\n\
+            %b -> con\n\
+            &con -> output";
+        // The undefined output is named debug, and is sent a low pulse on
+        // every odd press
+        assert_eq!(
+            presses_until_state(advanced, "debug", |m| m.get_pulse_counts().low() >= 2, 10),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_cascade_sizes() {
+        assert_eq!(
+            cascade_sizes(
+                "broadcaster -> a, b, c\n\
+                %a -> b\n\
+                %b -> c\n\
+                %c -> inv\n\
+                &inv -> a",
+                4
+            ),
+            vec![12; 4],
+        )
+    }
+
+    #[test]
+    fn test_try_part_1_oscillating() {
+        // Each conjunction always sends the other a pulse, so the first button
+        // press never finishes
+        assert_eq!(
+            try_part_1(
+                "broadcaster -> a\n\
+                &a -> b\n\
+                &b -> a",
+                1000
+            ),
+            Err("Button press 1 exceeded the limit of 1000 pulses".to_owned()),
+        )
+    }
+
+    #[test]
+    fn test_try_part_1_within_limit() {
+        assert_eq!(
+            try_part_1(
+                "broadcaster -> a, b, c\n\
+                %a -> b\n\
+                %b -> c\n\
+                %c -> inv\n\
+                &inv -> a",
+                12
+            ),
+            Ok(32000000),
+        )
+    }
+
+    #[test]
+    fn test_pulse_counts_after() {
+        let counts = pulse_counts_after(
+            "broadcaster -> a, b, c\n\
+            %a -> b\n\
+            %b -> c\n\
+            %c -> inv\n\
+            &inv -> a",
+            1,
+        );
+        assert_eq!((counts.low(), counts.high()), (8, 4));
+    }
+
+    #[test]
+    fn test_edge_pulses() {
+        let input = "broadcaster -> a, b, c\n\
+            %a -> b\n\
+            %b -> c\n\
+            %c -> inv\n\
+            &inv -> a";
+        assert_eq!(
+            edge_pulses(input, "broadcaster", "a", 2),
+            [(1, Pulse::Low), (2, Pulse::Low)]
+        );
+        // a only sends a pulse when it flips
+        assert_eq!(
+            edge_pulses(input, "a", "b", 2),
+            [
+                (1, Pulse::High),
+                (1, Pulse::Low),
+                (2, Pulse::High),
+                (2, Pulse::Low)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pulse_counter_arithmetic() {
+        let counters = vec![
+            PulseCounter::default() + Pulse::High,
+            PulseCounter::default() + Pulse::Low + Pulse::Low,
+            PulseCounter::default() + Pulse::High + Pulse::Low,
+        ];
+        let total: PulseCounter = counters.into_iter().sum();
+        assert_eq!((total.low(), total.high()), (3, 2));
+
+        let scaled = total * 1000;
+        assert_eq!((scaled.low(), scaled.high()), (3000, 2000));
+        assert_eq!(usize::from(scaled), 6000000);
+    }
+
     #[test]
     fn test_part_1_advanced() {
         assert_eq!(
@@ -773,6 +1267,24 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_part_1_brute_force() {
+        for input in [
+            "broadcaster -> a, b, c\n\
+            %a -> b\n\
+            %b -> c\n\
+            %c -> inv\n\
+            &inv -> a",
+            "broadcaster -> a\n\
+            %a -> inv, con\n\
+            &inv -> b\n\
+            %b -> con\n\
+            &con -> output",
+        ] {
+            assert_eq!(part_1_brute_force(input), part_1(input));
+        }
+    }
+
     #[test]
     fn test_part_2_brute_force() {
         assert_eq!(
@@ -781,12 +1293,46 @@ mod test {
                 %a -> inv, con\n\
                 &inv -> b\n\
                 %b -> con\n\
-                &con -> output"
+                &con -> output",
+                10
             ),
-            1
+            Ok(1)
+        )
+    }
+
+    #[test]
+    fn test_part_2_brute_force_budget() {
+        // The conjunction only ever sees a high pulse, so sends low pulses to
+        // the flip-flop, which never sends anything to the output
+        assert_eq!(
+            part_2_brute_force(
+                "broadcaster -> con\n\
+                &con -> a\n\
+                %a -> output",
+                10
+            ),
+            Err("No low pulse reached the sink within 10 button presses".to_owned())
         )
     }
 
+    #[test]
+    fn test_has_loop() {
+        assert!(!has_loop(
+            "broadcaster -> a\n\
+            %a -> inv, con\n\
+            &inv -> b\n\
+            %b -> con\n\
+            &con -> output"
+        ));
+        assert!(has_loop(
+            "broadcaster -> a\n\
+            %a -> b, con\n\
+            %b -> con\n\
+            %inv -> b\n\
+            &con -> inv, output"
+        ));
+    }
+
     #[test]
     fn test_part_2() {
         assert_eq!(
@@ -801,6 +1347,51 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_strict_unknown_output() {
+        let input = "broadcaster -> a\n\
+            %a -> ghost, output";
+        assert_eq!(
+            set_up_modules_strict(input).unwrap_err(),
+            "Module \"a\" outputs to unknown module \"ghost\""
+        );
+        // Lenient parsing sends it to the debug module instead
+        let modules = set_up_modules(input);
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[1].get_outputs(), [2, 2]);
+    }
+
+    #[test]
+    fn test_validate_input() {
+        assert_eq!(
+            validate_input(
+                "broadcaster -> a\n\
+                %a b"
+            ),
+            Err("Line 2 (\"%a b\") is missing \" -> \"".to_owned())
+        );
+        assert_eq!(
+            validate_input("a -> b"),
+            Err("Line 1 (\"a -> b\") has invalid module \"a\", expected \
+                \"broadcaster\" or a name prefixed with '%' or '&'"
+                .to_owned())
+        );
+        assert_eq!(
+            validate_input("broadcaster -> a,b"),
+            Err("Line 1 (\"broadcaster -> a,b\") has invalid output \"a,b\"".to_owned())
+        );
+        assert_eq!(
+            validate_input(
+                "broadcaster -> a\n\
+                %a -> inv, con\n\
+                &inv -> b\n\
+                %b -> con\n\
+                &con -> output"
+            ),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_part_2_loop() {
         let input =
@@ -809,7 +1400,7 @@ mod test {
             %b -> con\n\
             %inv -> b\n\
             &con -> inv, output";
-        let forced_value = part_2_brute_force(input);
+        let forced_value = part_2_brute_force(input, 10).unwrap();
         assert_eq!(forced_value, 3);
         assert_eq!(
             part_2(input),
@@ -29,7 +29,7 @@
 //! As such, we only need to calculate fills for the tiles with letters A-L,
 //! greatly reducing the required compute time
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     ops::{Add, Index, IndexMut, Neg},
 };
 
@@ -134,25 +134,67 @@ impl Chunk {
         self.0.get(r as usize, c as usize)
     }
 
-    /// Find the starting position in the chunk
+    /// Find the starting position in the chunk, making sure that it is in the
+    /// centre, as required by the infinite world calculations
     fn start_index(&self) -> (i32, i32) {
-        // Start is guaranteed to be in the centre of the world
-        let start = (self.dimensions() / 2, self.dimensions() / 2);
-        assert!(matches!(self[start], Tile::Start));
+        let start = find_start(self).expect("Expected exactly one start");
+        assert_eq!(
+            start,
+            (self.dimensions() / 2, self.dimensions() / 2),
+            "Start must be in the centre of the world"
+        );
         start
     }
 
-    /// Use BFS algorithm to fill the given world
-    fn fill(mut self, start: (i32, i32)) -> Self {
+    /// Build a larger chunk by tiling this one `repeats` times in every
+    /// direction around itself. Only the start in the central copy is kept.
+    fn tiled(&self, repeats: i32) -> Chunk {
+        let size = self.dimensions();
+        let width = size * (2 * repeats + 1);
+        Chunk(
+            Array2D::from_rows(
+                &(0..width)
+                    .map(|r| {
+                        (0..width)
+                            .map(|c| match self[(r % size, c % size)] {
+                                Tile::Start => Tile::Unvisited,
+                                tile => tile,
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec(),
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Count the positions reachable in exactly the given number of steps from
+    /// the given start, by tiling this chunk enough times that the edges of
+    /// the tiled area can't be reached
+    fn reachable_tiled(&self, start: (i32, i32), steps: usize) -> usize {
+        // Enough copies that walking in a straight line can't reach the edge
+        let repeats = steps as i32 / self.dimensions() + 1;
+        let offset = repeats * self.dimensions();
+
+        self.tiled(repeats)
+            .fill((start.0 + offset, start.1 + offset))
+            .num_tiles_visitable_at_depth(steps as i32)
+    }
+
+    /// Use BFS algorithm to find the number of steps needed to reach each
+    /// tile from the given start, or `None` if it can't be reached
+    fn distances(&self, start: (i32, i32)) -> Array2D<Option<usize>> {
+        let mut distances = Array2D::filled_with(None, self.0.num_rows(), self.0.num_columns());
         let mut q = VecDeque::default();
         q.push_back((start, 0));
         while let Some((location, depth)) = q.pop_front() {
+            let index = (location.0 as usize, location.1 as usize);
             // If we've already visited it, ignore it
-            if !self[location].is_visitable() {
+            if !self[location].is_visitable() || distances[index].is_some() {
                 continue;
             }
             // Otherwise, visit it now
-            self[location] = Tile::Visited(depth);
+            distances[index] = Some(depth);
             // Check all directions
             for direction in [NORTH, EAST, SOUTH, WEST] {
                 let result = location + direction;
@@ -164,6 +206,18 @@ impl Chunk {
             }
         }
 
+        distances
+    }
+
+    /// Fill the given world, marking each tile with the number of steps
+    /// needed to reach it
+    fn fill(mut self, start: (i32, i32)) -> Self {
+        for (index, distance) in self.distances(start).enumerate_row_major() {
+            if let Some(depth) = distance {
+                self.0[index] = Tile::Visited(*depth);
+            }
+        }
+
         self
     }
 
@@ -188,6 +242,18 @@ impl Chunk {
     }
 }
 
+/// Find the position of the start in the chunk, wherever it is, giving `None`
+/// unless there is exactly one start
+fn find_start(chunk: &Chunk) -> Option<(i32, i32)> {
+    chunk
+        .0
+        .enumerate_row_major()
+        .filter(|(_, tile)| **tile == Tile::Start)
+        .map(|((r, c), _)| (r as i32, c as i32))
+        .exactly_one()
+        .ok()
+}
+
 impl Index<(i32, i32)> for Chunk {
     type Output = Tile;
 
@@ -212,23 +278,24 @@ struct InfiniteWorld {
     num_even: usize,
     num_odd: usize,
 
-    // Centre approaches
-    world_north: Chunk,
-    world_east: Chunk,
-    world_south: Chunk,
-    world_west: Chunk,
-
-    // Corner approaches
-    world_north_east: Chunk,
-    world_north_west: Chunk,
-    world_south_east: Chunk,
-    world_south_west: Chunk,
+    /// The unexplored chunk that is repeated to form the world
+    chunk: Chunk,
+
+    /// The starting chunk, filled from the start
+    filled_centre: Chunk,
+
+    /// Chunks that have been filled so far, keyed by the position the fill
+    /// entered the chunk from
+    fills: HashMap<(i32, i32), Chunk>,
 }
 
 impl InfiniteWorld {
+    /// Create the infinite world made up of copies of the given chunk
+    ///
+    /// Chunks can be filled from any entry point, but the way that the
+    /// breakdown adds them up still relies on the start being in the centre
+    /// of the chunk, so this panics if it isn't.
     fn new(chunk: Chunk) -> Self {
-        let mid = chunk.dimensions() / 2;
-        let end = chunk.dimensions() - 1;
         let filled = chunk.clone().fill(chunk.start_index());
 
         InfiniteWorld {
@@ -238,118 +305,203 @@ impl InfiniteWorld {
             num_even: filled.num_tiles_visitable_at_depth(1000),
             num_odd: filled.num_tiles_visitable_at_depth(1001),
 
-            // Fill the other chunks for each approach direction
-            world_north: chunk.clone().fill((0, mid)),
-            world_east: chunk.clone().fill((mid, end)),
-            world_south: chunk.clone().fill((end, mid)),
-            world_west: chunk.clone().fill((mid, 0)),
-            world_north_east: chunk.clone().fill((0, end)),
-            world_north_west: chunk.clone().fill((0, 0)),
-            world_south_east: chunk.clone().fill((end, end)),
-            world_south_west: chunk.clone().fill((end, 0)),
+            chunk,
+            filled_centre: filled,
+            fills: HashMap::new(),
         }
     }
 
+    /// Return a reference to the chunk filled from the given entry point,
+    /// filling it if this entry point hasn't been used before
+    fn filled_from(&mut self, entry: (i32, i32)) -> &Chunk {
+        let chunk = &self.chunk;
+        self.fills
+            .entry(entry)
+            .or_insert_with(|| chunk.clone().fill(entry))
+    }
+
     /// Return a reference to the chunk filled from the given approach direction
     fn get_filled_chunk(
-        &self,
+        &mut self,
         approach: Direction,
         secondary_approach: Option<Direction>,
     ) -> &Chunk {
-        match approach {
+        let mid = self.chunk_size / 2;
+        let end = self.chunk_size - 1;
+
+        let entry = match approach {
             Direction::North => match secondary_approach {
                 Some(s) => match s {
-                    Direction::East => &self.world_north_east,
-                    Direction::West => &self.world_north_west,
+                    Direction::East => (0, end),
+                    Direction::West => (0, 0),
                     _ => panic!(),
                 },
-                None => &self.world_north,
+                None => (0, mid),
             },
             Direction::South => match secondary_approach {
                 Some(s) => match s {
-                    Direction::East => &self.world_south_east,
-                    Direction::West => &self.world_south_west,
+                    Direction::East => (end, end),
+                    Direction::West => (end, 0),
                     _ => panic!(),
                 },
-                None => &self.world_south,
+                None => (end, mid),
             },
             Direction::East => {
                 assert!(secondary_approach.is_none());
-                &self.world_east
+                (mid, end)
             }
             Direction::West => {
                 assert!(secondary_approach.is_none());
-                &self.world_west
+                (mid, 0)
             }
-        }
+        };
+        self.filled_from(entry)
     }
+}
 
-    /// Return the number of chunks that can be fully covered in a single
-    /// direction, excluding the starting chunk
-    fn num_chunks_covered_in_a_single_direction(&self, mut num_steps: usize) -> usize {
-        if (num_steps as i32) < self.chunk_size {
-            return 0;
-        }
-        // one half for the distance to the edge of the chunk,
-        // one half for the distance to the corner of the chunk
-        // Go down to nearest even, because the map is an odd width, meaning
-        // it'll be slightly less than the full width
-        num_steps -= self.chunk_size as usize - 1;
-
-        // Now return the number of times we can fully cross a chunk
-        num_steps / self.chunk_size as usize
+/// Return the number of chunks that can be fully covered in a single
+/// direction, excluding the starting chunk
+fn num_chunks_covered_in_a_single_direction(chunk_size: i32, mut num_steps: usize) -> usize {
+    if (num_steps as i32) < chunk_size {
+        return 0;
     }
+    // one half for the distance to the edge of the chunk,
+    // one half for the distance to the corner of the chunk
+    // Go down to nearest even, because the map is an odd width, meaning
+    // it'll be slightly less than the full width
+    num_steps -= chunk_size as usize - 1;
+
+    // Now return the number of times we can fully cross a chunk
+    num_steps / chunk_size as usize
+}
 
-    /// Return the number of steps remaining after walking to the closest
-    /// corner/edge of a chunk. Includes the step into the first cell of the
-    /// chunk.
-    ///
-    /// Examples
-    ///
-    ///     +--+
-    ///     |  |<------S
-    ///     +--+
-    ///
-    ///
-    ///        +-------S
-    ///        |
-    ///        v
-    ///     +--+
-    ///     |  |
-    ///     +--+
-    fn steps_remaining_at_chunk(&self, num_steps: usize, (row, col): (i32, i32)) -> i32 {
-        // Number of full chunks covered (excluding starting chunk)
-        let chunks_covered =
-            row.abs() + col.abs() - (if row == 0 { 0 } else { 1 } + if col == 0 { 0 } else { 1 });
-
-        // Steps required to leave the starting chunk
-        let steps_from_starting_chunk = (if row == 0 { 0 } else { self.chunk_size / 2 + 1 })
-            + (if col == 0 { 0 } else { self.chunk_size / 2 + 1 });
-
-        num_steps as i32 - chunks_covered * self.chunk_size - steps_from_starting_chunk
-    }
+/// Return the number of steps remaining after walking to the closest
+/// corner/edge of a chunk. Includes the step into the first cell of the
+/// chunk.
+///
+/// Examples
+///
+///     +--+
+///     |  |<------S
+///     +--+
+///
+///
+///        +-------S
+///        |
+///        v
+///     +--+
+///     |  |
+///     +--+
+fn steps_remaining_at_chunk(chunk_size: i32, num_steps: usize, (row, col): (i32, i32)) -> i32 {
+    // Number of full chunks covered (excluding starting chunk)
+    let chunks_covered =
+        row.abs() + col.abs() - (if row == 0 { 0 } else { 1 } + if col == 0 { 0 } else { 1 });
+
+    // Steps required to leave the starting chunk
+    let steps_from_starting_chunk = (if row == 0 { 0 } else { chunk_size / 2 + 1 })
+        + (if col == 0 { 0 } else { chunk_size / 2 + 1 });
+
+    num_steps as i32 - chunks_covered * chunk_size - steps_from_starting_chunk
 }
 
 #[aoc(day21, part1)]
 pub fn part_1(input: &str) -> usize {
     let chunk = Chunk::parse(input);
-    let start = chunk.start_index();
+    let start = find_start(&chunk).expect("Expected exactly one start");
     chunk.fill(start).num_tiles_visitable_at_depth(64)
 }
 
 fn num_positions_after_steps(input: &str, num_steps: usize) -> usize {
-    let world = InfiniteWorld::new(Chunk::parse(input));
+    part_2_breakdown(input, num_steps).total()
+}
 
-    let explored_width = world.num_chunks_covered_in_a_single_direction(num_steps);
+/// Some number of chunks that each have the same number of reachable tiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkTerm {
+    /// Number of chunks
+    pub num_chunks: usize,
+    /// Number of tiles reachable in each chunk
+    pub tiles_per_chunk: usize,
+}
 
+impl ChunkTerm {
+    /// Total number of tiles reachable in all of the chunks
+    pub fn total(&self) -> usize {
+        self.num_chunks * self.tiles_per_chunk
+    }
+}
+
+/// Each of the terms that make up the number of reachable tiles in the
+/// infinite world
+///
+/// The directional terms are ordered north, east, south, west, and the
+/// diagonal terms are ordered north-east, north-west, south-east, south-west.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Part2Breakdown {
+    /// Tiles reachable if the steps are too few to reach the edge of the
+    /// starting chunk, in which case all the other terms are empty
+    pub centre: usize,
+    /// Tiles reachable if the steps leave the starting chunk, but don't get
+    /// far enough for the diamond of chunks to form. These are counted by
+    /// simulating the steps directly, and all the other terms are empty.
+    pub nearby: usize,
+    /// Fully explored chunks with the same parity as the starting chunk
+    pub full_even: ChunkTerm,
+    /// Fully explored chunks with the opposite parity to the starting chunk
+    pub full_odd: ChunkTerm,
+    /// Chunks at each point of the diamond, entered from the middle of an edge
+    pub points: [ChunkTerm; 4],
+    /// Partially explored chunks directly outside the diamond's edges,
+    /// entered from a corner
+    pub close_diagonals: [ChunkTerm; 4],
+    /// Partially explored chunks just after those, entered from a corner
+    pub far_diagonals: [ChunkTerm; 4],
+}
+
+impl Part2Breakdown {
+    /// Total number of reachable tiles
+    pub fn total(&self) -> usize {
+        self.centre
+            + self.nearby
+            + self.full_even.total()
+            + self.full_odd.total()
+            + self
+                .points
+                .iter()
+                .chain(&self.close_diagonals)
+                .chain(&self.far_diagonals)
+                .map(ChunkTerm::total)
+                .sum::<usize>()
+    }
+}
+
+/// Returns each term that is added up to find the number of tiles reachable
+/// in the given number of steps
+pub fn part_2_breakdown(input: &str, num_steps: usize) -> Part2Breakdown {
+    breakdown_in_world(&mut InfiniteWorld::new(Chunk::parse(input)), num_steps)
+}
+
+fn breakdown_in_world(world: &mut InfiniteWorld, num_steps: usize) -> Part2Breakdown {
+    let explored_width = num_chunks_covered_in_a_single_direction(world.chunk_size, num_steps);
+
+    let mid = world.chunk_size / 2;
+    if num_steps as i32 <= mid {
+        // Can't reach the edge of the starting chunk - just use the part 1
+        // approach on the centre chunk, which we've already filled
+        return Part2Breakdown {
+            centre: world
+                .filled_centre
+                .num_tiles_visitable_at_depth(num_steps as i32),
+            ..Default::default()
+        };
+    }
     if explored_width == 0 {
-        // Didn't explore past the starting chunk - just use the part 1
-        // approach
-        let chunk = Chunk::parse(input);
-        let start = chunk.start_index();
-        return chunk
-            .fill(start)
-            .num_tiles_visitable_at_depth(num_steps as i32);
+        // Reached the neighbouring chunks, but not far enough for the diamond
+        // to form, so the steps are few enough to simulate directly
+        return Part2Breakdown {
+            nearby: world.chunk.reachable_tiled((mid, mid), num_steps),
+            ..Default::default()
+        };
     }
 
     // Create a diamond of fully explored chunks
@@ -368,45 +520,82 @@ fn num_positions_after_steps(input: &str, num_steps: usize) -> usize {
     let num_odd_chunks = (0..(explored_width)).sum::<usize>() * 2 + explored_width;
     let num_even_chunks = (1..=(explored_width)).sum::<usize>() * 2 + explored_width + 1;
 
-    // The sum of all chunks so far
-    let mut sum = world.num_even * num_even_chunks + world.num_odd * num_odd_chunks;
+    let full_even = ChunkTerm {
+        num_chunks: num_even_chunks,
+        tiles_per_chunk: world.num_even,
+    };
+    let full_odd = ChunkTerm {
+        num_chunks: num_odd_chunks,
+        tiles_per_chunk: world.num_odd,
+    };
 
     // Number of steps when entering the corner chunks at the end of the
     // diamond
     // This is the same at all corners
     let remaining_steps_at_points =
-        world.steps_remaining_at_chunk(num_steps, (0, explored_width as i32 + 1));
+        steps_remaining_at_chunk(world.chunk_size, num_steps, (0, explored_width as i32 + 1));
 
-    for dir in [NORTH, EAST, SOUTH, WEST] {
-        sum += world
+    let points = [NORTH, EAST, SOUTH, WEST].map(|dir| ChunkTerm {
+        num_chunks: 1,
+        tiles_per_chunk: world
             .get_filled_chunk(dir, None)
-            .num_tiles_visitable_at_depth(remaining_steps_at_points);
-    }
+            .num_tiles_visitable_at_depth(remaining_steps_at_points),
+    });
 
     // Number of partially-visited chunks on each diagonal
     let num_close_diagonals = explored_width;
     let num_far_diagonals = explored_width + 1;
 
     let remaining_steps_at_close_diagonal =
-        world.steps_remaining_at_chunk(num_steps, (1, explored_width as i32));
+        steps_remaining_at_chunk(world.chunk_size, num_steps, (1, explored_width as i32));
     let remaining_steps_at_far_diagonal =
-        world.steps_remaining_at_chunk(num_steps, (1, explored_width as i32 + 1));
+        steps_remaining_at_chunk(world.chunk_size, num_steps, (1, explored_width as i32 + 1));
+
+    let diagonals = [(NORTH, EAST), (NORTH, WEST), (SOUTH, EAST), (SOUTH, WEST)];
 
-    for diagonal in [(NORTH, EAST), (NORTH, WEST), (SOUTH, EAST), (SOUTH, WEST)] {
-        // Close diagonal
-        sum += world
+    let close_diagonals = diagonals.map(|diagonal| ChunkTerm {
+        num_chunks: num_close_diagonals,
+        tiles_per_chunk: world
             .get_filled_chunk(diagonal.0, Some(diagonal.1))
-            .num_tiles_visitable_at_depth(remaining_steps_at_close_diagonal)
-            * num_close_diagonals;
+            .num_tiles_visitable_at_depth(remaining_steps_at_close_diagonal),
+    });
 
-        // Far diagonal
-        sum += world
+    let far_diagonals = diagonals.map(|diagonal| ChunkTerm {
+        num_chunks: num_far_diagonals,
+        tiles_per_chunk: world
             .get_filled_chunk(diagonal.0, Some(diagonal.1))
-            .num_tiles_visitable_at_depth(remaining_steps_at_far_diagonal)
-            * num_far_diagonals;
+            .num_tiles_visitable_at_depth(remaining_steps_at_far_diagonal),
+    });
+
+    Part2Breakdown {
+        centre: 0,
+        nearby: 0,
+        full_even,
+        full_odd,
+        points,
+        close_diagonals,
+        far_diagonals,
     }
+}
+
+/// Count the positions reachable in exactly the given number of steps by
+/// simulating a finite section of the infinite world that is large enough that
+/// the edges can't be reached.
+///
+/// Unlike `num_positions_after_steps`, this makes no assumptions about the
+/// layout of the input, but takes time proportional to the square of the
+/// number of steps.
+pub fn reachable_bounded(input: &str, steps: usize) -> usize {
+    let chunk = Chunk::parse(input);
+    let start = find_start(&chunk).expect("Expected exactly one start");
+    chunk.reachable_tiled(start, steps)
+}
 
-    sum
+/// Returns the number of steps needed to reach each tile of the chunk from the
+/// given start, without leaving the chunk. Rocks and tiles that can't be
+/// reached are `None`.
+pub fn distance_field(input: &str, start: (i32, i32)) -> Array2D<Option<usize>> {
+    Chunk::parse(input).distances(start)
 }
 
 #[aoc(day21, part2)]
@@ -416,7 +605,11 @@ pub fn part_2(input: &str) -> usize {
 
 #[cfg(test)]
 mod test {
-    use super::num_positions_after_steps;
+    use super::{
+        breakdown_in_world, distance_field, find_start, num_chunks_covered_in_a_single_direction,
+        num_positions_after_steps, part_1, part_2_breakdown, reachable_bounded,
+        steps_remaining_at_chunk, Chunk, InfiniteWorld,
+    };
 
     fn num_positions_with_simple_input(num_steps: usize) -> usize {
         num_positions_after_steps(
@@ -447,4 +640,179 @@ mod test {
     fn test_part_2_wrap_next_cell() {
         assert_eq!(num_positions_with_simple_input(7), 64);
     }
+
+    #[test]
+    fn test_steps_one_chunk_away() {
+        // 3 steps to enter the next chunk, then 5 to cross each chunk after
+        assert_eq!(steps_remaining_at_chunk(5, 3, (0, 1)), 0);
+        assert_eq!(steps_remaining_at_chunk(5, 8, (0, 2)), 0);
+        // 4 steps to reach the corner of the starting chunk, then 5 to reach
+        // the far corner of the next chunk
+        assert_eq!(num_chunks_covered_in_a_single_direction(5, 8), 0);
+        assert_eq!(num_chunks_covered_in_a_single_direction(5, 9), 1);
+    }
+
+    #[test]
+    fn test_steps_landing_on_corner() {
+        assert_eq!(steps_remaining_at_chunk(5, 6, (1, 1)), 0);
+        assert_eq!(steps_remaining_at_chunk(5, 6, (-1, -1)), 0);
+        assert_eq!(steps_remaining_at_chunk(5, 11, (1, 2)), 0);
+    }
+
+    #[test]
+    fn test_zero_steps() {
+        assert_eq!(num_chunks_covered_in_a_single_direction(5, 0), 0);
+        assert_eq!(steps_remaining_at_chunk(5, 0, (0, 1)), -3);
+        assert_eq!(steps_remaining_at_chunk(5, 0, (1, 1)), -6);
+    }
+
+    #[test]
+    fn test_part_1_off_centre_start() {
+        let input = "S..\n\
+            ...\n\
+            ...";
+        assert_eq!(find_start(&Chunk::parse(input)), Some((0, 0)));
+        // Only the tiles an even distance from the corner can be reached
+        assert_eq!(part_1(input), 5);
+    }
+
+    #[test]
+    fn test_find_start_requires_one_start() {
+        assert_eq!(find_start(&Chunk::parse("...\n...")), None);
+        assert_eq!(find_start(&Chunk::parse("S.S\n...")), None);
+    }
+
+    #[test]
+    fn test_reachable_bounded_matches_diamond() {
+        let input = ".....\n\
+             .#.#.\n\
+             ..S..\n\
+             .#.#.\n\
+             .....";
+        // The diamond approach only holds when the steps end on the edge of
+        // a chunk, as they do in the real puzzle
+        for steps in [9, 14, 24, 39] {
+            assert_eq!(
+                reachable_bounded(input, steps),
+                num_positions_after_steps(input, steps),
+            );
+        }
+    }
+
+    #[test]
+    fn test_reachable_bounded_without_open_paths() {
+        let input = "...........\n\
+            .....###.#.\n\
+            .###.##..#.\n\
+            ..#.#...#..\n\
+            ....#.#....\n\
+            .##..S####.\n\
+            .##..#...#.\n\
+            .......##..\n\
+            .##.#.####.\n\
+            .##..##.##.\n\
+            ...........";
+        assert_eq!(reachable_bounded(input, 6), 16);
+        assert_eq!(reachable_bounded(input, 10), 50);
+        assert_eq!(reachable_bounded(input, 50), 1594);
+        assert_eq!(reachable_bounded(input, 100), 6536);
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let field = distance_field(
+            ".....\n\
+             .#.#.\n\
+             ..S..\n\
+             .#.#.\n\
+             .....",
+            (2, 2),
+        );
+        assert_eq!(
+            field.as_rows(),
+            [
+                [Some(4), Some(3), Some(2), Some(3), Some(4)],
+                [Some(3), None, Some(1), None, Some(3)],
+                [Some(2), Some(1), Some(0), Some(1), Some(2)],
+                [Some(3), None, Some(1), None, Some(3)],
+                [Some(4), Some(3), Some(2), Some(3), Some(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_from_any_entry_point() {
+        let input = ".....\n\
+             .#.#.\n\
+             ..S..\n\
+             .#.#.\n\
+             .....";
+        let mut world = InfiniteWorld::new(Chunk::parse(input));
+        let fresh = Chunk::parse(input).fill((1, 4));
+
+        for depth in 0..12 {
+            assert_eq!(
+                world
+                    .filled_from((1, 4))
+                    .num_tiles_visitable_at_depth(depth),
+                fresh.num_tiles_visitable_at_depth(depth),
+            );
+        }
+        // The fill is only done once for each entry point
+        assert_eq!(world.fills.len(), 1);
+    }
+
+    #[test]
+    fn test_steps_within_starting_chunk() {
+        let input = ".....\n\
+             .#.#.\n\
+             ..S..\n\
+             .#.#.\n\
+             .....";
+        let mut world = InfiniteWorld::new(Chunk::parse(input));
+
+        for steps in 0..9 {
+            assert_eq!(num_chunks_covered_in_a_single_direction(5, steps), 0);
+            assert_eq!(
+                breakdown_in_world(&mut world, steps).total(),
+                reachable_bounded(input, steps),
+                "{steps} steps"
+            );
+        }
+        // The centre chunk filled when creating the world was reused
+        assert!(world.fills.is_empty());
+    }
+
+    #[test]
+    fn test_part_2_breakdown() {
+        let input = ".....\n\
+             .#.#.\n\
+             ..S..\n\
+             .#.#.\n\
+             .....";
+        for steps in [9, 14, 24, 39] {
+            assert_eq!(
+                part_2_breakdown(input, steps).total(),
+                reachable_bounded(input, steps),
+            );
+        }
+
+        let breakdown = part_2_breakdown(input, 14);
+        assert_eq!(breakdown.centre, 0);
+        // Two chunks are covered in each direction, giving a diamond of 13
+        assert_eq!(num_chunks_covered_in_a_single_direction(5, 14), 2);
+        assert_eq!(breakdown.full_even.num_chunks, 9);
+        assert_eq!(breakdown.full_odd.num_chunks, 4);
+        assert!(breakdown.points.iter().all(|p| p.num_chunks == 1));
+        assert!(breakdown.close_diagonals.iter().all(|d| d.num_chunks == 2));
+        assert!(breakdown.far_diagonals.iter().all(|d| d.num_chunks == 3));
+
+        assert_eq!(part_2_breakdown(input, 2).centre, 5);
+
+        // Steps that only just leave the starting chunk
+        let breakdown = part_2_breakdown(input, 3);
+        assert_eq!(breakdown.centre, 0);
+        assert_eq!(breakdown.total(), reachable_bounded(input, 3));
+        assert_eq!(breakdown.total(), 16);
+    }
 }
@@ -1,6 +1,7 @@
 use std::{
+    cmp::Reverse,
     fmt::Debug,
-    ops::{Index, IndexMut, Range}, collections::HashSet,
+    ops::{Index, IndexMut, Range},
 };
 
 use array2d::Array2D;
@@ -18,6 +19,8 @@ struct Brick {
 }
 
 impl Brick {
+    /// Parse a brick from its endpoints, which may be given in either order
+    /// along each axis
     fn new(value: &str, id: usize) -> Self {
         let (xyz1, xyz2) = value.split_once('~').unwrap();
         let (x1, y1, z1): (usize, usize, usize) = xyz1
@@ -34,14 +37,43 @@ impl Brick {
         Brick {
             id,
             // Reduce z values by 1 to save space
-            z1: z1 - 1,
-            z2: z2 - 1,
-            x1,
-            x2,
-            y1,
-            y2,
+            z1: z1.min(z2) - 1,
+            z2: z1.max(z2) - 1,
+            x1: x1.min(x2),
+            x2: x1.max(x2),
+            y1: y1.min(y2),
+            y2: y1.max(y2),
         }
     }
+
+    /// Number of cubes that make up this brick
+    fn volume(&self) -> usize {
+        (self.x2 - self.x1 + 1) * (self.y2 - self.y1 + 1) * (self.z2 - self.z1 + 1)
+    }
+}
+
+fn parse_bricks(input: &str) -> Vec<Brick> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, b)| Brick::new(b, i))
+        .collect_vec()
+}
+
+/// How a brick must rest on the bricks below it to count as held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupportMode {
+    /// Any overlap with a brick directly below holds the brick up
+    AnyOverlap,
+    /// Every cell of the brick's base must rest on another brick
+    ///
+    /// This only changes which bricks count as holding each other up once the
+    /// pile has settled. Bricks still stop falling as soon as any part of them
+    /// lands, so a brick that is only partly supported stays where it is
+    /// without being held by anything. Since that gives no consistent answer
+    /// for which bricks fall in a chain reaction, those can only be counted in
+    /// `AnyOverlap` mode.
+    Full,
 }
 
 #[derive(Clone)]
@@ -55,6 +87,8 @@ struct BrickPile {
     space: Vec<Array2D<Option<usize>>>,
     /// Vec containing all bricks
     bricks: Vec<Brick>,
+    /// Rule used to decide whether a brick is held up
+    support_mode: SupportMode,
 }
 
 impl Index<(usize, usize, usize)> for BrickPile {
@@ -108,6 +142,10 @@ impl Debug for BrickPile {
 
 impl BrickPile {
     fn new(bricks: Vec<Brick>) -> Self {
+        BrickPile::with_support_mode(bricks, SupportMode::AnyOverlap)
+    }
+
+    fn with_support_mode(bricks: Vec<Brick>, support_mode: SupportMode) -> Self {
         let (x, y, z) = bricks
             .iter()
             .fold((1, 1, 1), |(mut x, mut y, mut z), curr| {
@@ -126,6 +164,7 @@ impl BrickPile {
         let mut pile = BrickPile {
             space: vec![Array2D::from_rows(&vec![vec![None; x]; y]).unwrap(); z],
             bricks: bricks.clone(),
+            support_mode,
         };
 
         // Fill each brick
@@ -140,6 +179,7 @@ impl BrickPile {
 
         // Now make all the bricks fall into place
         pile.stabilise();
+        debug_assert_eq!(pile.assert_no_floaters(), Ok(()));
 
         pile
     }
@@ -214,20 +254,37 @@ impl BrickPile {
             (brick.z1, brick.z2),
             Some(brick_index),
         );
-        self.fill(
-            (brick.x1, brick.x2),
-            (brick.y1, brick.y2),
-            (brick.z1, brick.z2),
-            Some(brick_index),
-        );
     }
 
     /// Make all bricks fall until they come to rest
+    ///
+    /// Bricks fall in order of their lowest z value, so that every brick
+    /// beneath a brick has already settled by the time it falls. Ties are
+    /// broken by x then y, although bricks with the same lowest z value can
+    /// never land on each other, so the tiebreak doesn't affect the result.
     fn stabilise(&mut self) {
-        // Since all the bricks are sorted by height - we can make them fall
-        // one by one and it'll work
-        for b in self.bricks.iter().sorted().cloned().collect_vec() {
-            self.make_brick_fall(b.id);
+        let fall_order = self
+            .bricks
+            .iter()
+            .sorted_by_key(|b| (b.z1, b.x1, b.y1))
+            .map(|b| b.id)
+            .collect_vec();
+        for b in fall_order {
+            self.make_brick_fall(b);
+        }
+    }
+
+    /// Make sure that every brick is resting on the ground or on another
+    /// brick, giving the id of the first brick found floating in the air
+    fn assert_no_floaters(&self) -> Result<(), usize> {
+        match self.bricks.iter().find(|brick| {
+            brick.z1 > 0
+                && self
+                    .find_bricks_in_region((brick.x1, brick.x2), (brick.y1, brick.y2), brick.z1 - 1)
+                    .is_empty()
+        }) {
+            Some(brick) => Err(brick.id),
+            None => Ok(()),
         }
     }
 
@@ -262,6 +319,15 @@ impl BrickPile {
         }
         let z = layer - 1;
 
+        if self.support_mode == SupportMode::Full {
+            let fully_supported = (brick.x1..=brick.x2)
+                .cartesian_product(brick.y1..=brick.y2)
+                .all(|(x, y)| self[(x, y, z)].is_some());
+            if !fully_supported {
+                return vec![];
+            }
+        }
+
         self.find_bricks_in_region((brick.x1, brick.x2), (brick.y1, brick.y2), z)
     }
 
@@ -277,72 +343,99 @@ impl BrickPile {
         self.find_bricks_in_region((brick.x1, brick.x2), (brick.y1, brick.y2), z)
     }
 
-    fn find_supporting_recursive(
-        &mut self,
-        brick_index: usize,
-    ) -> HashSet<usize> {
-        let mut res = self.do_find_supporting_recursive(brick_index, vec![brick_index]);
-
-        res.remove(&brick_index);
-
-        res
+    /// Return the indexes of all bricks that are supported only by this
+    /// brick, meaning they would fall immediately if it were removed
+    fn find_sole_supporting(&self, brick_index: usize) -> Vec<usize> {
+        self.find_supporting(brick_index)
+            .into_iter()
+            .filter(|&b| self.find_supports(b).len() == 1)
+            .collect_vec()
     }
 
-    /// Return the indexes of all bricks this brick supports, including
-    /// indirectly
-    fn do_find_supporting_recursive(
-        &mut self,
-        brick_index: usize,
-        lower_supports: Vec<usize>,
-    ) -> HashSet<usize> {
-        let bricks_we_will_destroy = self
-            .find_supporting(brick_index)
-            .into_iter()
-            // Only include supported bricks if we're the only support
-            .filter(|&b| {
-                let supports = self.find_supports(b);
-                supports
-                    .into_iter()
-                    // All supporting bricks for the current brick are ones
-                    // that the original brick directly supports
-                    .all(|support| lower_supports.contains(&support))
-            })
-            // Collect into a vec first to keep the borrow checker happy
-            // Otherwise, we're reading from and writing to the cache at
-            // the same time which is a big no no!
+    /// Return the number of other bricks that would fall if each brick were
+    /// removed
+    ///
+    /// A brick falls when every chain of supports leading down to the ground
+    /// goes through the removed brick. Working upwards, we find the closest
+    /// such brick for each brick (its immediate dominator), using the results
+    /// already found for its supports. The bricks that fall when a brick is
+    /// removed are then all the bricks below it in the tree of dominators.
+    ///
+    /// Panics if the pile uses `SupportMode::Full`, since partly supported
+    /// bricks would be treated as resting on the ground.
+    fn fall_counts(&self) -> Vec<usize> {
+        assert_eq!(
+            self.support_mode,
+            SupportMode::AnyOverlap,
+            "Chain reactions can only be counted when any overlap supports a brick"
+        );
+
+        let order = self
+            .brick_indexes()
+            .sorted_by_key(|&b| self[b].z1)
             .collect_vec();
 
-        // Cache miss, calculate value
-        let ret = bricks_we_will_destroy
-            .iter()
-            // And for each one, expand it into the values that it supports
-            .flat_map(|&b| {
-                // Bricks that are in our "tower of supported bricks"
-                let curr_lower_supports = lower_supports
-                    .iter()
-                    .chain(bricks_we_will_destroy.iter())
-                    .copied()
-                    .collect_vec();
-                let mut res = self.do_find_supporting_recursive(b, curr_lower_supports);
-                // Don't forget to include this one too
-                res.insert(b);
-                res
-            })
-            .collect();
+        // `None` represents the ground
+        let mut dominators: Vec<Option<usize>> = vec![None; self.bricks.len()];
+        let mut depths = vec![0; self.bricks.len()];
+
+        for &b in &order {
+            let dominator = self
+                .find_supports(b)
+                .into_iter()
+                .map(Some)
+                .reduce(|a, b| common_dominator(&dominators, &depths, a, b))
+                .flatten();
+            dominators[b] = dominator;
+            depths[b] = dominator.map_or(0, |d| depths[d] + 1);
+        }
+
+        // Add up the size of each subtree, starting from the top
+        let mut counts = vec![0; self.bricks.len()];
+        for &b in order.iter().rev() {
+            if let Some(d) = dominators[b] {
+                counts[d] += counts[b] + 1;
+            }
+        }
 
-        ret
+        counts
     }
 }
 
+/// Find the closest brick that dominates both of the given bricks, or `None`
+/// if only the ground does
+fn common_dominator(
+    dominators: &[Option<usize>],
+    depths: &[usize],
+    mut a: Option<usize>,
+    mut b: Option<usize>,
+) -> Option<usize> {
+    while a != b {
+        match (a, b) {
+            (Some(x), Some(y)) => {
+                if depths[x] >= depths[y] {
+                    a = dominators[x];
+                } else {
+                    b = dominators[y];
+                }
+            }
+            _ => return None,
+        }
+    }
+    a
+}
+
 #[aoc(day22, part1)]
 pub fn part_1(input: &str) -> usize {
-    let bricks: Vec<Brick> = input
-        .lines()
-        .enumerate()
-        .map(|(i, b)| Brick::new(b, i))
-        .collect_vec();
+    part_1_with_support_mode(input, SupportMode::AnyOverlap)
+}
 
-    let pile = BrickPile::new(bricks);
+/// Count the bricks that can be safely disintegrated, using the given rule to
+/// decide whether a brick is held up by the bricks below it
+pub fn part_1_with_support_mode(input: &str, support_mode: SupportMode) -> usize {
+    let bricks = parse_bricks(input);
+
+    let pile = BrickPile::with_support_mode(bricks, support_mode);
 
     // Now find all the bricks that are only supporting bricks that have at least 2 supports
     pile.brick_indexes()
@@ -356,26 +449,131 @@ pub fn part_1(input: &str) -> usize {
         .count()
 }
 
+/// Count the total number of bricks that fall across every single-brick
+/// removal
+///
+/// Rather than simulating each removal, this uses the single bottom-up pass in
+/// `BrickPile::fall_counts`, which reuses the results for each brick's
+/// supports, so it stays fast for large piles.
 #[aoc(day22, part2)]
 pub fn part_2(input: &str) -> usize {
-    let bricks: Vec<Brick> = input
-        .lines()
+    let bricks = parse_bricks(input);
+
+    let pile = BrickPile::new(bricks);
+
+    pile.fall_counts().into_iter().sum()
+}
+
+/// Find the brick which makes the most other bricks fall when it is removed,
+/// giving its id and the number of bricks that fall
+///
+/// If multiple bricks cause equally large chain reactions, the one with the
+/// lowest id is chosen.
+pub fn max_chain_reaction(input: &str) -> (usize, usize) {
+    let pile = BrickPile::new(parse_bricks(input));
+
+    pile.fall_counts()
+        .into_iter()
         .enumerate()
-        .map(|(i, b)| Brick::new(b, i))
-        .collect_vec();
+        .map(|(b, count)| (pile[b].id, count))
+        .min_by_key(|&(id, count)| (Reverse(count), id))
+        .expect("There should be at least one brick")
+}
+
+/// Shape of the cascade caused by removing a brick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CascadeKind {
+    /// Removing the brick doesn't make any other bricks fall
+    Isolated,
+    /// Removing the brick makes exactly one brick fall directly
+    Linear,
+    /// Removing the brick makes multiple bricks fall directly
+    Branching,
+}
+
+/// Categorise the cascade caused by removing each brick, based on how many
+/// bricks it is the only support for
+pub fn cascade_category(input: &str) -> Vec<CascadeKind> {
+    let pile = BrickPile::new(parse_bricks(input));
+
+    pile.brick_indexes()
+        .map(|b| match pile.find_sole_supporting(b).len() {
+            0 => CascadeKind::Isolated,
+            1 => CascadeKind::Linear,
+            _ => CascadeKind::Branching,
+        })
+        .collect_vec()
+}
+
+/// Number of bricks resting directly on each brick once they have settled
+pub fn direct_support_counts(input: &str) -> Vec<usize> {
+    let pile = BrickPile::new(parse_bricks(input));
+
+    pile.brick_indexes()
+        .map(|b| pile.find_supporting(b).len())
+        .collect_vec()
+}
+
+/// Ids of the bricks directly holding up the brick with the given id once the
+/// bricks have settled, sorted by id
+pub fn supporters_of(input: &str, brick_id: usize) -> Vec<usize> {
+    let pile = BrickPile::new(parse_bricks(input));
+
+    pile.find_supports(brick_id)
+        .into_iter()
+        .map(|b| pile[b].id)
+        .sorted()
+        .collect_vec()
+}
+
+/// Ids of the bricks resting directly on the brick with the given id once the
+/// bricks have settled, sorted by id
+pub fn supported_of(input: &str, brick_id: usize) -> Vec<usize> {
+    let pile = BrickPile::new(parse_bricks(input));
 
-    let mut pile = BrickPile::new(bricks);
+    pile.find_supporting(brick_id)
+        .into_iter()
+        .map(|b| pile[b].id)
+        .sorted()
+        .collect_vec()
+}
 
-    // dbg!(&pile);
+/// Height of the pile once the bricks have settled, which is the number of
+/// layers from the ground up to the top of the highest brick
+pub fn pile_height(input: &str) -> usize {
+    let pile = BrickPile::new(parse_bricks(input));
 
     pile.brick_indexes()
-        .map(|b| pile.find_supporting_recursive(b).len())
-        .sum()
+        .map(|b| pile[b].z2 + 1)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Total volume of all the bricks in the input
+pub fn total_volume(input: &str) -> usize {
+    parse_bricks(input).iter().map(Brick::volume).sum()
 }
 
 #[cfg(test)]
 mod test {
-    use super::{part_1, part_2};
+    use std::collections::HashSet;
+
+    use itertools::Itertools;
+
+    use super::{
+        cascade_category, direct_support_counts, max_chain_reaction, parse_bricks, part_1,
+        part_1_with_support_mode, part_2, pile_height, supported_of, supporters_of, total_volume,
+        BrickPile, CascadeKind, SupportMode,
+    };
+    use crate::test_util::Lcg;
+
+    const SAMPLE: &str = "1,0,1~1,2,1\n\
+        0,0,2~2,0,2\n\
+        0,2,3~2,2,3\n\
+        0,0,4~0,2,4\n\
+        2,0,5~2,2,5\n\
+        0,1,6~2,1,6\n\
+        1,1,8~1,1,9";
 
     #[test]
     fn test_simple() {
@@ -403,34 +601,12 @@ mod test {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(
-            part_1(
-                "1,0,1~1,2,1\n\
-                0,0,2~2,0,2\n\
-                0,2,3~2,2,3\n\
-                0,0,4~0,2,4\n\
-                2,0,5~2,2,5\n\
-                0,1,6~2,1,6\n\
-                1,1,8~1,1,9"
-            ),
-            5,
-        )
+        assert_eq!(part_1(SAMPLE), 5)
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(
-            part_2(
-                "1,0,1~1,2,1\n\
-                0,0,2~2,0,2\n\
-                0,2,3~2,2,3\n\
-                0,0,4~0,2,4\n\
-                2,0,5~2,2,5\n\
-                0,1,6~2,1,6\n\
-                1,1,8~1,1,9"
-            ),
-            7,
-        )
+        assert_eq!(part_2(SAMPLE), 7)
     }
 
     #[test]
@@ -445,6 +621,169 @@ mod test {
         )
     }
 
-    // FIXME: For some reason, the answer is too low
-    // Hunt for a case where bricks that would fall are missed
+    #[test]
+    fn test_input_order_doesnt_matter() {
+        let shuffled = "0,1,6~2,1,6\n\
+            0,0,4~0,2,4\n\
+            1,1,8~1,1,9\n\
+            0,0,2~2,0,2\n\
+            2,0,5~2,2,5\n\
+            1,0,1~1,2,1\n\
+            0,2,3~2,2,3";
+        assert_eq!(part_1(shuffled), 5);
+        assert_eq!(part_2(shuffled), 7);
+    }
+
+    #[test]
+    fn test_volume_conserved() {
+        let pile = BrickPile::new(parse_bricks(SAMPLE));
+        let occupied: usize = pile
+            .space
+            .iter()
+            .map(|layer| layer.elements_row_major_iter().filter(|c| c.is_some()).count())
+            .sum();
+        assert_eq!(total_volume(SAMPLE), 20);
+        assert_eq!(occupied, total_volume(SAMPLE));
+    }
+
+    #[test]
+    fn test_pile_height() {
+        // G settles on top of F, taking up layers 5 and 6
+        let pile = BrickPile::new(parse_bricks(SAMPLE));
+        assert_eq!(pile[6].z2 + 1, 6);
+        assert_eq!(pile_height(SAMPLE), 6);
+    }
+
+    #[test]
+    fn test_cascade_category() {
+        use CascadeKind::*;
+        assert_eq!(
+            cascade_category(SAMPLE),
+            [Branching, Isolated, Isolated, Isolated, Isolated, Linear, Isolated]
+        );
+    }
+
+    #[test]
+    fn test_no_floaters() {
+        let mut pile = BrickPile::new(parse_bricks(SAMPLE));
+        assert_eq!(pile.assert_no_floaters(), Ok(()));
+
+        // Lift G up by one layer, leaving a gap beneath it
+        let g = pile[6].clone();
+        pile.fill((g.x1, g.x2), (g.y1, g.y2), (g.z1, g.z2), None);
+        pile[6].z1 += 1;
+        pile[6].z2 += 1;
+        pile.fill((g.x1, g.x2), (g.y1, g.y2), (g.z1 + 1, g.z2 + 1), Some(6));
+        assert_eq!(pile.assert_no_floaters(), Err(6));
+    }
+
+    #[test]
+    fn test_max_chain_reaction() {
+        // Removing A makes every other brick fall
+        assert_eq!(max_chain_reaction(SAMPLE), (0, 6));
+    }
+
+    #[test]
+    fn test_supporters() {
+        // D is held up by B and C
+        assert_eq!(supporters_of(SAMPLE, 3), [1, 2]);
+        // A holds up B and C
+        assert_eq!(supported_of(SAMPLE, 0), [1, 2]);
+        assert!(supporters_of(SAMPLE, 0).is_empty());
+        assert!(supported_of(SAMPLE, 6).is_empty());
+    }
+
+    #[test]
+    fn test_direct_support_counts() {
+        // A supports B and C
+        assert_eq!(direct_support_counts(SAMPLE), [2, 2, 2, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_reversed_endpoints() {
+        let bricks = parse_bricks("2,2,2~0,0,2\n1,1,5~1,1,3");
+        assert_eq!((bricks[0].x1, bricks[0].x2), (0, 2));
+        assert_eq!((bricks[0].y1, bricks[0].y2), (0, 2));
+        assert_eq!((bricks[1].z1, bricks[1].z2), (2, 4));
+
+        let pile = BrickPile::new(bricks);
+        assert_eq!((pile[0].z1, pile[0].z2), (0, 0));
+        assert_eq!((pile[1].z1, pile[1].z2), (1, 3));
+    }
+
+    #[test]
+    fn test_support_modes() {
+        // The second brick only rests half of its base on the first
+        let input = "0,0,1~0,1,1\n0,0,2~1,1,2";
+        let bricks = parse_bricks(input);
+
+        let pile = BrickPile::with_support_mode(bricks.clone(), SupportMode::AnyOverlap);
+        assert_eq!(pile.find_supports(1), vec![0]);
+        let pile = BrickPile::with_support_mode(bricks, SupportMode::Full);
+        assert_eq!(pile.find_supports(1), Vec::<usize>::new());
+
+        assert_eq!(part_1_with_support_mode(input, SupportMode::AnyOverlap), 1);
+        assert_eq!(part_1_with_support_mode(input, SupportMode::Full), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Chain reactions can only be counted")]
+    fn test_fall_counts_rejects_full_support() {
+        let bricks = parse_bricks("0,0,1~0,1,1\n0,0,2~1,1,2");
+        BrickPile::with_support_mode(bricks, SupportMode::Full).fall_counts();
+    }
+
+    /// Generate a pile of bricks within a 3x3 footprint, spaced out vertically
+    /// so that they don't overlap before falling
+    fn generated_pile(num_bricks: usize) -> String {
+        let mut rng = Lcg::new(12345);
+        let mut next = |n| rng.below(n);
+
+        // Make sure both horizontal dimensions are the full width
+        let mut lines = vec!["0,0,1~2,0,1".to_owned(), "0,0,2~0,2,2".to_owned()];
+        for i in 0..num_bricks {
+            let (x, y, z) = (next(3), next(3), 4 * i + 3);
+            let len = next(3);
+            let end = match next(3) {
+                0 => ((x + len).min(2), y, z),
+                1 => (x, (y + len).min(2), z),
+                _ => (x, y, z + len),
+            };
+            lines.push(format!("{x},{y},{z}~{},{},{}", end.0, end.1, end.2));
+        }
+        lines.join("\n")
+    }
+
+    /// Count the bricks that fall when each brick is removed, by simulating
+    /// each removal separately
+    fn naive_fall_counts(pile: &BrickPile) -> Vec<usize> {
+        let order = pile
+            .brick_indexes()
+            .sorted_by_key(|&b| pile[b].z1)
+            .collect_vec();
+        pile.brick_indexes()
+            .map(|removed| {
+                let mut fallen = HashSet::from([removed]);
+                for &b in &order {
+                    let supports = pile.find_supports(b);
+                    if !supports.is_empty() && supports.iter().all(|s| fallen.contains(s)) {
+                        fallen.insert(b);
+                    }
+                }
+                fallen.len() - 1
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_fall_counts_match_naive() {
+        let input = generated_pile(200);
+        let pile = BrickPile::new(parse_bricks(&input));
+        let naive = naive_fall_counts(&pile);
+        assert_eq!(pile.fall_counts(), naive);
+        assert_eq!(part_2(&input), naive.iter().sum::<usize>());
+
+        let pile = BrickPile::new(parse_bricks(SAMPLE));
+        assert_eq!(pile.fall_counts(), naive_fall_counts(&pile));
+    }
 }
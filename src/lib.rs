@@ -9,6 +9,7 @@ pub mod day03;
 pub mod day04;
 pub mod day05;
 pub mod day06;
+pub mod day07;
 pub mod day07_part_1;
 pub mod day07_part_2;
 pub mod day08;
@@ -25,4 +26,7 @@ pub mod day20;
 pub mod day21;
 pub mod day22;
 
+#[cfg(test)]
+mod test_util;
+
 aoc_lib! { year = 2023 }
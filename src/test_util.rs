@@ -0,0 +1,63 @@
+//! Helpers shared between the tests for each day
+
+use std::fmt::Debug;
+
+use itertools::Itertools;
+
+/// A tiny linear congruential generator, so that randomised tests are
+/// reproducible without needing an extra dependency
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    /// Generate a number in the range `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+/// Generate random hands of five cards, drawing each card from `cards`
+pub fn random_hands(rng: &mut Lcg, num_hands: usize, cards: &[u8]) -> Vec<String> {
+    (0..num_hands)
+        .map(|_| {
+            (0..5)
+                .map(|_| cards[rng.below(cards.len())] as char)
+                .collect()
+        })
+        .collect_vec()
+}
+
+/// Check that random hands of the same type are ordered card by card, where
+/// `strength` lists every card from weakest to strongest
+pub fn assert_same_type_ordered_by_cards<H>(
+    cards: &[u8],
+    strength: &str,
+    same_type: impl Fn(&H, &H) -> bool,
+) where
+    H: Ord + Debug + for<'a> From<&'a str>,
+{
+    let hands = random_hands(&mut Lcg::new(7), 200, cards);
+    let strengths = |hand: &str| {
+        hand.chars()
+            .map(|c| strength.find(c).unwrap())
+            .collect_vec()
+    };
+
+    for (a, b) in hands.iter().tuple_combinations() {
+        let (hand_a, hand_b) = (H::from(a.as_str()), H::from(b.as_str()));
+        if same_type(&hand_a, &hand_b) {
+            assert_eq!(
+                hand_a.cmp(&hand_b),
+                strengths(a).cmp(&strengths(b)),
+                "{a} vs {b}"
+            );
+        }
+    }
+}